@@ -0,0 +1,267 @@
+use std::path::PathBuf;
+use anyhow::{bail, Result};
+use bytesize::ByteSize;
+use clap::ValueEnum;
+use crate::core::{cleaner, discovery, lint, projects, quarantine, registry, scanner, CleanupItem, ItemStatus, RunMode};
+use crate::core::discovery::System;
+use crate::core::lint::{Finding, Severity};
+use crate::core::quarantine::QuarantineRun;
+use crate::core::registry::{Definitions, Target};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+/// Loads the definitions. Project mode only takes the exclude and protected paths
+/// and settings from them, so it also works without any definitions file.
+pub fn load_definitions(config: &[PathBuf], projects: &[PathBuf]) -> Result<Definitions> {
+    if !projects.is_empty() && registry::definition_files(config)?.is_empty() {
+        return Ok(Definitions::default());
+    }
+    registry::load_definitions(config)
+}
+
+/// The targets to scan: the rules matching `system`, or the build artifacts found
+/// below `projects` when any are given.
+pub fn find_targets(definitions: &Definitions, system: &System, projects: &[PathBuf]) -> Vec<Target> {
+    if projects.is_empty() {
+        registry::filter_rules(definitions, system)
+    } else {
+        projects::find_artifacts(projects, &definitions.exclude)
+    }
+}
+
+pub fn run_scan(config: &[PathBuf], projects: &[PathBuf], format: OutputFormat) -> Result<()> {
+    let system = discovery::detect_system();
+    let definitions = load_definitions(config, projects)?;
+    let targets = find_targets(&definitions, &system, projects);
+
+    let mut items = scanner::scan_targets(targets);
+    items.sort_by(|a, b| a.category.cmp(&b.category).then(a.path.cmp(&b.path)));
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&items)?),
+        OutputFormat::Table => print_table(&items),
+    }
+
+    Ok(())
+}
+
+fn print_table(items: &[CleanupItem]) {
+    if items.is_empty() {
+        println!("No cleanable items found.");
+        return;
+    }
+
+    let name_width = items.iter().map(|i| i.name.len()).max().unwrap_or(0).max("NAME".len());
+
+    println!("  {:<name_width$}  {:>10}  {:>10}  {:>9}  PATH", "NAME", "SIZE", "ON DISK", "MODIFIED");
+    // Items come sorted by category, which is listed above its items like in the TUI.
+    let mut category = None;
+    for item in items {
+        if category != Some(&item.category) {
+            println!();
+            println!("{}", item.category);
+            category = Some(&item.category);
+        }
+        println!(
+            "  {:<name_width$}  {:>10}  {:>10}  {:>9}  {}",
+            item.name,
+            ByteSize(item.size_bytes).to_string(),
+            ByteSize(item.allocated_bytes).to_string(),
+            item.age().unwrap_or_else(|| "-".to_string()),
+            item.location()
+        );
+    }
+
+    let total: u64 = items.iter().map(|i| i.size_bytes).sum();
+    let allocated: u64 = items.iter().map(|i| i.allocated_bytes).sum();
+    println!();
+    println!("Total: {} ({} on disk) in {} items", ByteSize(total), ByteSize(allocated), items.len());
+}
+
+/// Cleans every item belonging to `groups`. Returns `false` if any item failed.
+pub fn run_clean(config: &[PathBuf], projects: &[PathBuf], groups: &[String], mode: RunMode) -> Result<bool> {
+    let system = discovery::detect_system();
+    let definitions = load_definitions(config, projects)?;
+
+    let known: Vec<&str> = if projects.is_empty() {
+        definitions.groups.iter().map(|g| g.id.as_str()).collect()
+    } else {
+        projects::ARTIFACT_KINDS.iter().map(|kind| kind.id).collect()
+    };
+    let unknown: Vec<&str> = groups
+        .iter()
+        .filter(|id| !known.contains(&id.as_str()))
+        .map(|id| id.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        bail!("Unknown group id(s): {}", unknown.join(", "));
+    }
+
+    if let Err(e) = quarantine::purge_expired(definitions.quarantine_retention()) {
+        eprintln!("Warning: failed to purge expired quarantine runs: {:#}", e);
+    }
+
+    let targets = find_targets(&definitions, &system, projects)
+        .into_iter()
+        .filter(|t| groups.contains(&t.group_id))
+        .collect();
+
+    let mut items = scanner::scan_targets(targets);
+    items.sort_by(|a, b| a.category.cmp(&b.category).then(a.path.cmp(&b.path)));
+
+    if items.is_empty() {
+        println!("Nothing to clean for the selected groups.");
+        return Ok(true);
+    }
+
+    if mode == RunMode::DryRun {
+        println!("Dry-run: no files will be deleted. Pass --trash or --yes to clean.");
+    }
+
+    let run = match mode {
+        RunMode::Quarantine => Some(QuarantineRun::new()?),
+        _ => None,
+    };
+
+    let protected = definitions.protected_paths();
+    let ctx = cleaner::CleanContext::new(mode, run.as_ref(), &protected);
+
+    let mut freed = 0;
+    for item in &mut items {
+        if let Err(e) = cleaner::clean_item(item, &ctx) {
+            item.status = ItemStatus::Failed(format!("{:#}", e));
+        }
+
+        let (label, reason) = match &item.status {
+            ItemStatus::Deleted => match mode {
+                RunMode::DryRun if item.command.is_some() => ("would run", None),
+                RunMode::DryRun => ("would delete", None),
+                RunMode::Trash => ("trashed", None),
+                RunMode::Quarantine => ("quarantined", None),
+                RunMode::Delete => ("deleted", None),
+            },
+            ItemStatus::Executed(output) => ("ran", output.lines().last()),
+            ItemStatus::Skipped(reason) => ("skipped", Some(reason.as_str())),
+            ItemStatus::Failed(reason) => ("failed", Some(reason.as_str())),
+            ItemStatus::Scanned => ("unchanged", None),
+        };
+        if item.status.is_cleaned() {
+            freed += item.size_bytes;
+        }
+
+        let size = ByteSize(item.size_bytes).to_string();
+        match reason {
+            Some(reason) => println!("{:<12}  {:>10}  {} ({})", label, size, item.location(), reason),
+            None => println!("{:<12}  {:>10}  {}", label, size, item.location()),
+        }
+    }
+
+    let failed = items.iter().filter(|i| matches!(i.status, ItemStatus::Failed(_))).count();
+    println!();
+    match mode {
+        RunMode::DryRun => println!("Would free {} from {} items", ByteSize(freed), items.len()),
+        RunMode::Trash => println!("Moved {} to trash ({} failed)", ByteSize(freed), failed),
+        RunMode::Quarantine => println!(
+            "Quarantined {} ({} failed), undo with: tidytui undo {}",
+            ByteSize(freed),
+            failed,
+            run.as_ref().map(|r| r.id.as_str()).unwrap_or_default()
+        ),
+        RunMode::Delete => println!("Freed {} ({} failed)", ByteSize(freed), failed),
+    }
+
+    Ok(failed == 0)
+}
+
+/// Lints every definitions file that would be loaded, then the result of merging
+/// them. Returns `false` if any file fails to parse or has errors.
+pub fn run_check_config(config: &[PathBuf]) -> Result<bool> {
+    let system = discovery::detect_system();
+    let paths = registry::definition_files(config)?;
+    if paths.is_empty() {
+        bail!("No definitions found in any of the search paths");
+    }
+
+    println!("System: {}, installed tools: {}", system, system.tools.installed().join(", "));
+    println!();
+
+    let (mut errors, mut warnings) = (0, 0);
+    for path in &paths {
+        println!("{}", path.display());
+        let findings = match registry::parse_definitions(path) {
+            Ok(definitions) => {
+                let mut findings = lint::lint(&definitions, &system);
+                if registry::is_untrusted(path, config) && definitions.has_commands() {
+                    findings.push(Finding {
+                        severity: Severity::Warning,
+                        message: "rules running a command are ignored in the current directory's definitions, pass the file with --config to use them".to_string(),
+                    });
+                }
+                findings
+            }
+            Err(e) => vec![Finding { severity: Severity::Error, message: format!("{:#}", e) }],
+        };
+        if findings.is_empty() {
+            println!("  ok");
+        }
+        for finding in findings {
+            let label = match finding.severity {
+                Severity::Error => {
+                    errors += 1;
+                    "error"
+                }
+                Severity::Warning => {
+                    warnings += 1;
+                    "warning"
+                }
+                Severity::Note => "note",
+            };
+            println!("  {}: {}", label, finding.message);
+        }
+    }
+
+    // Problems that only show up once the files are layered, e.g. an override without a name.
+    if errors == 0 {
+        if let Err(e) = registry::load_definitions(config) {
+            println!("merged definitions");
+            println!("  error: {:#}", e);
+            errors += 1;
+        }
+    }
+
+    println!();
+    println!("Checked {} files: {} errors, {} warnings", paths.len(), errors, warnings);
+    Ok(errors == 0)
+}
+
+/// Lists quarantined runs, or restores one. Returns `false` if any item could not be restored.
+pub fn run_undo(config: &[PathBuf], run_id: Option<&str>, list: bool) -> Result<bool> {
+    if let Ok(definitions) = registry::load_definitions(config) {
+        if let Err(e) = quarantine::purge_expired(definitions.quarantine_retention()) {
+            eprintln!("Warning: failed to purge expired quarantine runs: {:#}", e);
+        }
+    }
+
+    if list {
+        let runs = quarantine::list_runs()?;
+        if runs.is_empty() {
+            println!("No quarantined runs.");
+        }
+        for run in runs {
+            println!("{}", run);
+        }
+        return Ok(true);
+    }
+
+    let report = quarantine::undo(run_id)?;
+    for (path, reason) in &report.conflicts {
+        println!("{:<12}  {} ({})", "kept", path.display(), reason);
+    }
+    println!("Restored {} items from run {}", report.restored, report.run_id);
+
+    Ok(report.conflicts.is_empty())
+}
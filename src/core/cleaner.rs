@@ -1,13 +1,181 @@
 use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::SystemTime;
 use anyhow::{Result, Context};
-use crate::core::{CleanupItem, ItemStatus};
+use thiserror::Error;
+use walkdir::WalkDir;
+use crate::core::{discovery, scanner, trash, CleanMode, CleanupItem, ItemStatus, RunMode};
+use crate::core::filter::FileFilter;
+use crate::core::protected::ProtectedPaths;
+use crate::core::quarantine::QuarantineRun;
 
-pub fn clean_item(item: &mut CleanupItem, dry_run: bool) -> Result<()> {
-    if !item.path.exists() {
+/// Returned internally when a clean is cancelled between two files.
+#[derive(Debug, Error)]
+#[error("cancelled")]
+pub struct Cancelled;
+
+/// Callback receiving each removed file and the bytes it freed.
+pub type FileCallback<'a> = &'a dyn Fn(&Path, u64);
+
+/// How a clean is carried out, and hooks for callers to follow or stop it.
+#[derive(Clone, Copy)]
+pub struct CleanContext<'a> {
+    pub mode: RunMode,
+    /// The run removed items are moved into; required for `RunMode::Quarantine`.
+    pub quarantine: Option<&'a QuarantineRun>,
+    /// Paths that are refused whatever the item says.
+    pub protected: &'a ProtectedPaths,
+    /// Checked before every file. Once set, the current item stops and is marked cancelled.
+    pub cancel: Option<&'a AtomicBool>,
+    /// Called with every file (or moved entry) and its size in bytes right after it is removed.
+    pub on_file: Option<FileCallback<'a>>,
+    /// Device of the item being cleaned, when mounts below it must be left alone.
+    root_device: Option<u64>,
+}
+
+impl<'a> CleanContext<'a> {
+    pub fn new(mode: RunMode, quarantine: Option<&'a QuarantineRun>, protected: &'a ProtectedPaths) -> Self {
+        CleanContext { mode, quarantine, protected, cancel: None, on_file: None, root_device: None }
+    }
+
+    /// Whether `path` leads onto another mount than the item being cleaned.
+    fn on_other_mount(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        self.root_device.is_some_and(|device| {
+            metadata.dev() != device || (metadata.is_dir() && discovery::is_mount_point(path))
+        })
+    }
+
+    /// Like [`Self::on_other_mount`] for walk entries, never excluding the walk root itself.
+    fn crosses_mount(&self, entry: &walkdir::DirEntry) -> bool {
+        self.root_device.is_some()
+            && entry.depth() > 0
+            && entry.metadata().is_ok_and(|m| self.on_other_mount(entry.path(), &m))
+    }
+
+    fn check_cancelled(&self) -> Result<()> {
+        match self.cancel.is_some_and(|c| c.load(Ordering::Relaxed)) {
+            true => Err(Cancelled.into()),
+            false => Ok(()),
+        }
+    }
+
+    fn file_done(&self, path: &Path, bytes: u64) {
+        if let Some(on_file) = self.on_file {
+            on_file(path, bytes);
+        }
+    }
+
+    /// Removes a single file or directory tree according to the mode.
+    fn remove(&self, path: &Path) -> Result<()> {
+        match (self.mode, self.quarantine) {
+            (RunMode::Trash, _) => self.move_entry(path, trash::move_to_trash),
+            (RunMode::Quarantine, Some(run)) => self.move_entry(path, |p| run.move_in(p)),
+            (RunMode::Quarantine, None) => Err(anyhow::anyhow!("No quarantine run to move items into")),
+            _ => delete_path(path, self),
+        }
+    }
+
+    /// Moves a whole entry away in one step, reporting the bytes it held.
+    fn move_entry(&self, path: &Path, move_to: impl Fn(&Path) -> Result<()>) -> Result<()> {
+        self.check_cancelled()?;
+        // A mount would move along with its parent and be deleted once the Trash
+        // or quarantine is emptied.
+        if self.root_device.is_some() && scanner::contains_mount(path) {
+            anyhow::bail!("contains a mount point, which cannot be moved");
+        }
+        // Only worth measuring when someone is watching progress.
+        let bytes = match self.on_file {
+            Some(_) => scanner::scan_path(path, &FileFilter::default(), self.root_device.is_none()).apparent,
+            None => 0,
+        };
+        move_to(path)?;
+        self.file_done(path, bytes);
+        Ok(())
+    }
+}
+
+/// Progress reported by [`clean_items_streaming`]. Items are identified by the
+/// index they were submitted with.
+#[derive(Debug)]
+pub enum CleanEvent {
+    Started(usize),
+    /// A file was removed, with the bytes it freed.
+    File(PathBuf, u64),
+    Finished(usize, ItemStatus),
+}
+
+/// Cleans `items` one after another, sending progress over `tx`. Setting `cancel`
+/// stops after the current file; items not reached yet are left untouched.
+/// The channel closes once cleaning has stopped.
+pub fn clean_items_streaming(
+    items: Vec<(usize, CleanupItem)>,
+    mode: RunMode,
+    quarantine: Option<QuarantineRun>,
+    protected: ProtectedPaths,
+    cancel: Arc<AtomicBool>,
+    tx: Sender<CleanEvent>,
+) {
+    let on_file = |path: &Path, bytes: u64| {
+        let _ = tx.send(CleanEvent::File(path.to_path_buf(), bytes));
+    };
+    let ctx = CleanContext {
+        mode,
+        quarantine: quarantine.as_ref(),
+        protected: &protected,
+        cancel: Some(&cancel),
+        on_file: Some(&on_file),
+        root_device: None,
+    };
+
+    for (idx, mut item) in items {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let _ = tx.send(CleanEvent::Started(idx));
+        if let Err(e) = clean_item(&mut item, &ctx) {
+            item.status = ItemStatus::Failed(format!("{:#}", e));
+        }
+        let _ = tx.send(CleanEvent::Finished(idx, item.status));
+    }
+}
+
+/// Cleans `item` according to `ctx.mode`, updating its status.
+pub fn clean_item(item: &mut CleanupItem, ctx: &CleanContext) -> Result<()> {
+    if let Some(command) = item.command.clone() {
+        item.status = match ctx.mode {
+            RunMode::DryRun => ItemStatus::Deleted,
+            RunMode::Trash | RunMode::Quarantine => {
+                ItemStatus::Skipped("command actions cannot be undone, use danger mode".to_string())
+            }
+            RunMode::Delete => run_command(&command)?,
+        };
         return Ok(());
     }
 
-    if dry_run {
+    let Ok(metadata) = fs::symlink_metadata(&item.path) else {
+        item.status = ItemStatus::Skipped("path no longer exists".to_string());
+        return Ok(());
+    };
+
+    // Whatever a symlinked target points at lies outside the rule, so leave it be.
+    // The same goes for matches the scan found behind a symlinked directory.
+    if metadata.is_symlink() || item.symlink_target.is_some() {
+        let target = fs::read_link(&item.path).ok().or(item.symlink_target.clone()).unwrap_or_default();
+        item.status = ItemStatus::Skipped(format!("symlink to {}, not followed", target.display()));
+        return Ok(());
+    }
+
+    // Checked before dry runs too, so they never promise to clean a protected path.
+    let filtered = filters_below(&item.path, &item.filter, item.cross_mounts);
+    let removes_path = item.mode == CleanMode::Whole && !filtered;
+    ctx.protected.check(&item.path, &item.root, removes_path)?;
+
+    if ctx.mode == RunMode::DryRun {
         // Just simulate
         item.status = ItemStatus::Deleted; // Visually indicate it *would* be deleted or add a "DryRun" status
         // For MVP, lets just print to helpful logs or stdout if we weren't in TUI mode,
@@ -21,12 +189,373 @@ pub fn clean_item(item: &mut CleanupItem, dry_run: bool) -> Result<()> {
         return Ok(());
     }
 
-    if item.path.is_file() {
-        fs::remove_file(&item.path).context("Failed to delete file")?;
-    } else if item.path.is_dir() {
-        fs::remove_dir_all(&item.path).context("Failed to delete directory")?;
+    if ctx.mode == RunMode::Trash && trash::is_in_trash(&item.path) {
+        item.status = ItemStatus::Skipped("already in trash".to_string());
+        return Ok(());
     }
 
-    item.status = ItemStatus::Deleted;
+    let ctx = &CleanContext {
+        root_device: (!item.cross_mounts).then(|| metadata.dev()),
+        ..*ctx
+    };
+    let keep_root = item.mode == CleanMode::Contents;
+    let result = if filtered {
+        remove_matching(&item.path, &item.filter, keep_root, ctx)
+    } else if metadata.is_dir() && keep_root {
+        remove_contents(&item.path, ctx)
+    } else {
+        ctx.remove(&item.path)
+    };
+
+    match result {
+        Ok(()) => item.status = ItemStatus::Deleted,
+        Err(e) if e.is::<Cancelled>() => item.status = ItemStatus::Skipped("cancelled".to_string()),
+        Err(e) => return Err(e),
+    }
     Ok(())
 }
+
+/// Whether `filter` can leave anything below `root` in place, i.e. it has an age
+/// limit or excludes something inside the item. If not, the item is removed like an
+/// unfiltered one, moving whole directories rather than every file on its own.
+fn filters_below(root: &Path, filter: &FileFilter, cross_mounts: bool) -> bool {
+    if filter.is_empty() {
+        return false;
+    }
+    filter.older_than.is_some()
+        || WalkDir::new(root)
+            .follow_root_links(false)
+            .same_file_system(!cross_mounts)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .any(|e| filter.is_excluded(e.path(), root))
+}
+
+/// Runs a command action through `sh -c`, capturing its output and exit status.
+fn run_command(command: &str) -> Result<ItemStatus> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .with_context(|| format!("Failed to run '{}'", command))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        return Ok(ItemStatus::Executed(format!("{}{}", stdout, stderr).trim().to_string()));
+    }
+
+    let exit = match output.status.code() {
+        Some(code) => format!("exit status {}", code),
+        None => "killed by signal".to_string(),
+    };
+    Ok(ItemStatus::Failed(match stderr.trim() {
+        "" => exit,
+        stderr => format!("{}: {}", exit, stderr),
+    }))
+}
+
+/// Permanently deletes a file or directory tree one file at a time, so progress can
+/// be reported and cancellation honoured. Symlinks are unlinked, never followed, and
+/// mount points are left in place unless the item may cross filesystems.
+fn delete_path(path: &Path, ctx: &CleanContext) -> Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    delete_entry(path, &metadata, ctx)
+}
+
+fn delete_entry(path: &Path, metadata: &fs::Metadata, ctx: &CleanContext) -> Result<()> {
+    if !metadata.is_dir() {
+        ctx.check_cancelled()?;
+        fs::remove_file(path).context("Failed to delete file")?;
+        ctx.file_done(path, file_size(metadata));
+        return Ok(());
+    }
+
+    // Children are checked before descending, so nothing on a skipped mount is touched.
+    let mut kept_mount = false;
+    for entry in fs::read_dir(path).context("Failed to read directory")? {
+        let entry = entry.context("Failed to read directory")?;
+        let child = entry.path();
+        let child_metadata = entry.metadata().context("Failed to read metadata")?;
+        if ctx.on_other_mount(&child, &child_metadata) {
+            kept_mount = true;
+            continue;
+        }
+        delete_entry(&child, &child_metadata, ctx)?;
+    }
+
+    match fs::remove_dir(path) {
+        // Still holds the mount point that was skipped.
+        Err(_) if kept_mount => Ok(()),
+        result => result.context("Failed to delete directory"),
+    }
+}
+
+/// Bytes counted for a removed entry, matching what the scanner counts.
+fn file_size(metadata: &fs::Metadata) -> u64 {
+    if metadata.is_file() { metadata.len() } else { 0 }
+}
+
+/// Removes the children of `root` that are on its filesystem, leaving the directory itself with its owner and
+/// permissions untouched.
+fn remove_contents(root: &Path, ctx: &CleanContext) -> Result<()> {
+    let mut failed = 0;
+    let mut first_error = None;
+    for entry in fs::read_dir(root).context("Failed to read directory")? {
+        let entry = entry?;
+        if entry.metadata().is_ok_and(|m| ctx.on_other_mount(&entry.path(), &m)) {
+            continue;
+        }
+        if let Err(e) = ctx.remove(&entry.path()) {
+            if e.is::<Cancelled>() {
+                return Err(e);
+            }
+            failed += 1;
+            first_error.get_or_insert_with(|| format!("{}: {:#}", entry.path().display(), e));
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(anyhow::anyhow!("Failed to delete {} entries (first: {})", failed, e)),
+        None => Ok(()),
+    }
+}
+
+/// Removes only the files below `root` that match `filter`, skipping excluded subtrees.
+/// Directories that matched before cleaning and are left empty are pruned, including
+/// `root` unless `keep_root` is set.
+fn remove_matching(
+    root: &Path,
+    filter: &FileFilter,
+    keep_root: bool,
+    ctx: &CleanContext,
+) -> Result<()> {
+    let now = SystemTime::now();
+    let entries: Vec<_> = WalkDir::new(root)
+        .follow_root_links(false)
+        .into_iter()
+        .filter_entry(|e| !filter.is_excluded(e.path(), root) && !ctx.crosses_mount(e))
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let matched = filter.matches(&e.metadata().ok()?, now);
+            Some((e, matched))
+        })
+        .collect();
+
+    let mut failed = 0;
+    let mut first_error = None;
+    for (entry, _) in entries.iter().filter(|(e, matched)| *matched && !e.file_type().is_dir()) {
+        if let Err(e) = ctx.remove(entry.path()) {
+            if e.is::<Cancelled>() {
+                return Err(e);
+            }
+            failed += 1;
+            first_error.get_or_insert_with(|| format!("{}: {:#}", entry.path().display(), e));
+        }
+    }
+
+    // Children come after their parents in walk order, so reverse to prune bottom-up.
+    let prunable = |e: &walkdir::DirEntry| e.file_type().is_dir() && !(keep_root && e.depth() == 0);
+    for (entry, _) in entries.iter().rev().filter(|(e, matched)| *matched && prunable(e)) {
+        // Only succeeds for directories that are now empty.
+        let _ = fs::remove_dir(entry.path());
+    }
+
+    match first_error {
+        Some(e) => Err(anyhow::anyhow!("Failed to delete {} files (first: {})", failed, e)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::FileTimes;
+    use std::os::unix::fs::symlink;
+    use std::time::Duration;
+    use crate::core::filter::ExcludePattern;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// A fresh directory below the system temp dir, removed by the caller.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tidytui-cleaner-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn exclude(patterns: &[&str]) -> FileFilter {
+        let exclude = patterns.iter().map(|p| ExcludePattern::try_from(p.to_string()).unwrap()).collect();
+        FileFilter { older_than: None, exclude }
+    }
+
+    fn item(path: &Path, mode: CleanMode, filter: FileFilter) -> CleanupItem {
+        CleanupItem {
+            group_id: "test".to_string(),
+            name: "Test".to_string(),
+            category: "Test".to_string(),
+            path: path.to_path_buf(),
+            command: None,
+            size_bytes: 0,
+            allocated_bytes: 0,
+            modified: None,
+            selected: true,
+            status: ItemStatus::Scanned,
+            mode,
+            filter,
+            cross_mounts: false,
+            root: path.to_path_buf(),
+            symlink_target: None,
+        }
+    }
+
+    fn delete(item: &mut CleanupItem) {
+        let protected = ProtectedPaths::new(&[]);
+        clean_item(item, &CleanContext::new(RunMode::Delete, None, &protected)).unwrap();
+    }
+
+    /// Writes `path`, creating its parents, and dates it `age` back.
+    fn write_aged(path: &Path, age: Duration) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"data").unwrap();
+        set_age(path, age);
+    }
+
+    fn set_age(path: &Path, age: Duration) {
+        let time = SystemTime::now() - age;
+        let times = FileTimes::new().set_accessed(time).set_modified(time);
+        fs::File::open(path).unwrap().set_times(times).unwrap();
+    }
+
+    #[test]
+    fn contents_mode_keeps_the_root() {
+        let dir = temp_dir("contents");
+        let cache = dir.join("cache");
+        write_aged(&cache.join("sub/file"), Duration::ZERO);
+        write_aged(&cache.join("file"), Duration::ZERO);
+
+        let mut contents = item(&cache, CleanMode::Contents, FileFilter::default());
+        delete(&mut contents);
+        let kept_root = cache.is_dir();
+        let emptied = fs::read_dir(&cache).unwrap().next().is_none();
+        let mut whole = item(&cache, CleanMode::Whole, FileFilter::default());
+        delete(&mut whole);
+        let removed = !cache.exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(kept_root && emptied);
+        assert_eq!(contents.status, ItemStatus::Deleted);
+        assert!(removed);
+    }
+
+    #[test]
+    fn excluded_subtrees_survive() {
+        let dir = temp_dir("excluded");
+        write_aged(&dir.join("keep/nested/file"), Duration::ZERO);
+        write_aged(&dir.join("drop/nested/file"), Duration::ZERO);
+        write_aged(&dir.join("notes.keep"), Duration::ZERO);
+        write_aged(&dir.join("data.bin"), Duration::ZERO);
+
+        delete(&mut item(&dir, CleanMode::Whole, exclude(&["keep", "*.keep"])));
+        let kept = dir.join("keep/nested/file").exists() && dir.join("notes.keep").exists();
+        let dropped = !dir.join("drop").exists() && !dir.join("data.bin").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(kept);
+        assert!(dropped);
+    }
+
+    #[test]
+    fn older_than_removes_stale_files_and_prunes_emptied_dirs() {
+        let dir = temp_dir("older-than");
+        write_aged(&dir.join("stale/file"), 60 * DAY);
+        write_aged(&dir.join("mixed/old"), 60 * DAY);
+        write_aged(&dir.join("mixed/new"), DAY);
+        fs::create_dir(dir.join("empty")).unwrap();
+        // Written into just now, so date the directories back like in a real cache.
+        for stale_dir in ["stale", "mixed", "empty"] {
+            set_age(&dir.join(stale_dir), 60 * DAY);
+        }
+        fs::create_dir(dir.join("fresh")).unwrap();
+
+        let filter = FileFilter { older_than: Some(30 * DAY), exclude: Vec::new() };
+        delete(&mut item(&dir, CleanMode::Contents, filter));
+        let stale_gone = !dir.join("stale").exists() && !dir.join("mixed/old").exists();
+        let new_kept = dir.join("mixed/new").exists();
+        let empty_pruned = !dir.join("empty").exists();
+        let fresh_kept = dir.join("fresh").is_dir();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(stale_gone);
+        assert!(new_kept);
+        assert!(empty_pruned);
+        assert!(fresh_kept);
+    }
+
+    #[test]
+    fn symlinks_are_not_followed() {
+        let dir = temp_dir("symlinks");
+        write_aged(&dir.join("outside/file"), Duration::ZERO);
+        fs::create_dir(dir.join("cache")).unwrap();
+        symlink(dir.join("outside"), dir.join("cache/link")).unwrap();
+        symlink(dir.join("outside"), dir.join("linked-cache")).unwrap();
+
+        let mut linked = item(&dir.join("linked-cache"), CleanMode::Whole, FileFilter::default());
+        delete(&mut linked);
+        delete(&mut item(&dir.join("cache"), CleanMode::Contents, FileFilter::default()));
+        let link_removed = fs::symlink_metadata(dir.join("cache/link")).is_err();
+        let target_kept = dir.join("outside/file").exists() && dir.join("linked-cache").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(linked.status, ItemStatus::Skipped(_)), "{:?}", linked.status);
+        assert!(link_removed);
+        assert!(target_kept);
+    }
+
+    #[test]
+    fn nested_mounts_are_left_alone() {
+        let dir = temp_dir("mounts");
+        let mount = dir.join("cache/mnt");
+        fs::create_dir_all(&mount).unwrap();
+        write_aged(&dir.join("cache/file"), Duration::ZERO);
+        let mounted = Command::new("mount").args(["-t", "tmpfs", "tmpfs"]).arg(&mount).output();
+        if !mounted.is_ok_and(|o| o.status.success()) {
+            // Needs privileges to mount, which test environments often lack.
+            fs::remove_dir_all(&dir).unwrap();
+            return;
+        }
+        fs::write(mount.join("file"), b"data").unwrap();
+
+        let protected = ProtectedPaths::new(&[]);
+        let mut contents = item(&dir.join("cache"), CleanMode::Contents, FileFilter::default());
+        let result = clean_item(&mut contents, &CleanContext::new(RunMode::Delete, None, &protected));
+        let mount_kept = mount.join("file").exists();
+        let file_removed = !dir.join("cache/file").exists();
+        let _ = Command::new("umount").arg(&mount).status();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_ok(), "{:?}", result);
+        assert!(mount_kept);
+        assert!(file_removed);
+    }
+
+    #[test]
+    fn filters_below_only_when_something_is_kept() {
+        let dir = temp_dir("filters-below");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/data.bin"), b"data").unwrap();
+        fs::write(dir.join("sub/notes.keep"), b"keep").unwrap();
+
+        let unrelated = filters_below(&dir, &exclude(&["*.lock", "/var/tmp/tidytui-elsewhere"]), false);
+        let inside = filters_below(&dir, &exclude(&["*.keep"]), false);
+        let aged = FileFilter { older_than: Some(Duration::from_secs(60)), exclude: Vec::new() };
+        let older_than = filters_below(&dir, &aged, false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(!filters_below(&dir, &FileFilter::default(), false));
+        assert!(!unrelated);
+        assert!(inside);
+        assert!(older_than);
+    }
+}
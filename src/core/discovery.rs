@@ -1,3 +1,10 @@
+use std::env;
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use os_release::OsRelease;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -7,19 +14,267 @@ pub enum OsType {
     Debian,
     Fedora,
     OpenSuse,
+    NixOs,
+    Alpine,
+    Gentoo,
+    Void,
+    Solus,
+    ClearLinux,
     Unknown(String),
 }
 
-pub fn detect_os() -> OsType {
-    match OsRelease::new() {
-        Ok(release) => match release.id.as_str() {
-            "arch" | "manjaro" | "endeavouros" => OsType::Arch,
-            "ubuntu" | "pop" | "mint" => OsType::Ubuntu,
-            "debian" => OsType::Debian,
-            "fedora" | "centos" | "rhel" => OsType::Fedora,
-            "opensuse" | "opensuse-tumbleweed" | "opensuse-leap" | "suse" => OsType::OpenSuse,
-            other => OsType::Unknown(other.to_string()),
-        },
-        Err(_) => OsType::Unknown("unknown".to_string()),
+impl fmt::Display for OsType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OsType::Arch => "Arch Linux",
+            OsType::Ubuntu => "Ubuntu",
+            OsType::Debian => "Debian",
+            OsType::Fedora => "Fedora",
+            OsType::OpenSuse => "openSUSE",
+            OsType::NixOs => "NixOS",
+            OsType::Alpine => "Alpine Linux",
+            OsType::Gentoo => "Gentoo",
+            OsType::Void => "Void Linux",
+            OsType::Solus => "Solus",
+            OsType::ClearLinux => "Clear Linux",
+            OsType::Unknown(id) => id,
+        };
+        f.write_str(name)
+    }
+}
+
+/// What rules are matched against: the running distribution, installed tools
+/// and the environment it runs in.
+#[derive(Debug, Clone)]
+pub struct System {
+    pub os: OsType,
+    pub tools: Tools,
+    pub environment: Environment,
+}
+
+impl fmt::Display for System {
+    /// E.g. `Fedora (container: podman, immutable)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.os)?;
+        let env = &self.environment;
+        let labels: Vec<String> = env
+            .container
+            .as_ref()
+            .map(|kind| format!("container: {}", kind))
+            .into_iter()
+            .chain(env.wsl.then(|| "WSL".to_string()))
+            .chain(env.immutable.then(|| "immutable".to_string()))
+            .collect();
+        if !labels.is_empty() {
+            write!(f, " ({})", labels.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn detect_system() -> System {
+    let release = OsRelease::new().ok();
+    System {
+        os: release.as_ref().map_or(OsType::Unknown("unknown".to_string()), os_type),
+        tools: Tools::detect(),
+        environment: Environment::detect(release.as_ref()),
+    }
+}
+
+/// Detects the running distribution from os-release. Derivatives not known by
+/// their `ID` (e.g. Rocky, Zorin or CachyOS) are resolved through `ID_LIKE`,
+/// which lists the distributions they build on, closest first.
+fn os_type(release: &OsRelease) -> OsType {
+    std::iter::once(release.id.as_str())
+        .chain(release.id_like.split_whitespace())
+        .find_map(os_type_for_id)
+        .unwrap_or_else(|| OsType::Unknown(release.id.clone()))
+}
+
+fn os_type_for_id(id: &str) -> Option<OsType> {
+    match id {
+        "arch" | "manjaro" | "endeavouros" => Some(OsType::Arch),
+        "ubuntu" | "pop" | "mint" => Some(OsType::Ubuntu),
+        "debian" => Some(OsType::Debian),
+        "fedora" | "centos" | "rhel" => Some(OsType::Fedora),
+        "opensuse" | "opensuse-tumbleweed" | "opensuse-leap" | "suse" => Some(OsType::OpenSuse),
+        "nixos" => Some(OsType::NixOs),
+        "alpine" | "postmarketos" => Some(OsType::Alpine),
+        "gentoo" | "funtoo" => Some(OsType::Gentoo),
+        "void" => Some(OsType::Void),
+        "solus" => Some(OsType::Solus),
+        "clear-linux-os" => Some(OsType::ClearLinux),
+        _ => None,
+    }
+}
+
+/// Facts about the environment a rule can be restricted to with `when`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fact {
+    Container,
+    Wsl,
+    Immutable,
+}
+
+impl Fact {
+    pub const ALL: &'static [(&'static str, Fact)] =
+        &[("container", Fact::Container), ("wsl", Fact::Wsl), ("immutable", Fact::Immutable)];
+
+    pub fn name(self) -> &'static str {
+        Self::ALL.iter().find(|(_, fact)| *fact == self).map(|(name, _)| *name).unwrap_or_default()
+    }
+}
+
+/// Where TidyTUI runs, beyond the distribution.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    /// The container engine, e.g. `docker` or `podman`, when running inside a container.
+    pub container: Option<String>,
+    /// Running under the Windows Subsystem for Linux.
+    pub wsl: bool,
+    /// An image-based system whose packages are not managed in place, like
+    /// Fedora Silverblue and Kinoite (ostree) or SteamOS.
+    pub immutable: bool,
+}
+
+impl Environment {
+    pub fn detect(release: Option<&OsRelease>) -> Self {
+        Environment {
+            container: detect_container(),
+            wsl: env::var_os("WSL_DISTRO_NAME").is_some()
+                || fs::read_to_string("/proc/sys/kernel/osrelease")
+                    .is_ok_and(|kernel| kernel.to_lowercase().contains("microsoft")),
+            immutable: Path::new("/run/ostree-booted").exists()
+                || release.is_some_and(|r| r.id == "steamos"),
+        }
+    }
+
+    pub fn has(&self, fact: Fact) -> bool {
+        match fact {
+            Fact::Container => self.container.is_some(),
+            Fact::Wsl => self.wsl,
+            Fact::Immutable => self.immutable,
+        }
+    }
+}
+
+fn detect_container() -> Option<String> {
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    // Set by systemd-nspawn, LXC and others, and by podman for the container's processes.
+    env::var("container").ok().filter(|kind| !kind.is_empty())
+}
+
+/// Tools probed once at startup. Rules may require any other program as well,
+/// which is then looked up when the rule is matched.
+pub const KNOWN_TOOLS: &[&str] = &[
+    "pacman", "apt", "dnf", "zypper", "flatpak", "snap", "docker", "podman", "nix", "cargo", "npm", "pip",
+];
+
+/// The installed programs among [`KNOWN_TOOLS`].
+#[derive(Debug, Clone, Default)]
+pub struct Tools {
+    installed: Vec<&'static str>,
+}
+
+impl Tools {
+    pub fn detect() -> Self {
+        let installed = KNOWN_TOOLS.iter().copied().filter(|tool| find_tool(tool).is_some()).collect();
+        Tools { installed }
+    }
+
+    pub fn installed(&self) -> &[&'static str] {
+        &self.installed
+    }
+
+    /// Whether `program` is installed, using the probe results for known tools.
+    pub fn has(&self, program: &str) -> bool {
+        if KNOWN_TOOLS.contains(&program) {
+            self.installed.contains(&program)
+        } else {
+            find_in_path(program).is_some()
+        }
+    }
+}
+
+fn find_tool(tool: &str) -> Option<PathBuf> {
+    match tool {
+        // Many distributions only ship pip for Python 3 as `pip3`.
+        "pip" => find_in_path("pip").or_else(|| find_in_path("pip3")),
+        _ => find_in_path(tool),
+    }
+}
+
+/// Looks `program` up in `$PATH` like a shell would, returning the executable found.
+pub fn find_in_path(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable(&path).then_some(path);
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether `path` is the root of a mount, including bind mounts of a directory
+/// from the same filesystem, which share its device number. Symlinks are not followed.
+pub fn is_mount_point(path: &Path) -> bool {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statx is plain old data, so all zeroes is a valid value.
+    let mut stx: libc::statx = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is NUL-terminated and stx is a writable statx buffer.
+    let ret = unsafe {
+        libc::statx(libc::AT_FDCWD, c_path.as_ptr(), libc::AT_SYMLINK_NOFOLLOW, 0, &mut stx)
+    };
+    let mount_root = libc::STATX_ATTR_MOUNT_ROOT as u64;
+    ret == 0 && stx.stx_attributes_mask & mount_root != 0 && stx.stx_attributes & mount_root != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(id: &str, id_like: &str) -> OsRelease {
+        OsRelease { id: id.to_string(), id_like: id_like.to_string(), ..OsRelease::default() }
+    }
+
+    #[test]
+    fn known_ids_resolve_directly() {
+        assert_eq!(os_type(&release("debian", "")), OsType::Debian);
+        assert_eq!(os_type(&release("manjaro", "arch")), OsType::Arch);
+        assert_eq!(os_type(&release("alpine", "")), OsType::Alpine);
+        assert_eq!(os_type(&release("clear-linux-os", "")), OsType::ClearLinux);
+    }
+
+    #[test]
+    fn derivatives_resolve_through_id_like() {
+        assert_eq!(os_type(&release("rocky", "rhel centos fedora")), OsType::Fedora);
+        assert_eq!(os_type(&release("almalinux", "rhel centos fedora")), OsType::Fedora);
+        assert_eq!(os_type(&release("zorin", "ubuntu debian")), OsType::Ubuntu);
+        assert_eq!(os_type(&release("kali", "debian")), OsType::Debian);
+        assert_eq!(os_type(&release("cachyos", "arch")), OsType::Arch);
+    }
+
+    #[test]
+    fn closest_id_like_entry_wins() {
+        assert_eq!(os_type(&release("elementary", "ubuntu debian")), OsType::Ubuntu);
+        assert_eq!(os_type(&release("custom", "unknown-base debian")), OsType::Debian);
+    }
+
+    #[test]
+    fn unknown_distribution_keeps_its_id() {
+        assert_eq!(os_type(&release("someos", "")), OsType::Unknown("someos".to_string()));
+        assert_eq!(os_type(&release("someos", "otheros")), OsType::Unknown("someos".to_string()));
     }
 }
@@ -0,0 +1,150 @@
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use crate::core::scanner;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A glob that protects paths from being scanned or cleaned.
+///
+/// Patterns starting with `/`, `~` or a variable like `$XDG_CACHE_HOME` match absolute
+/// paths (see [`scanner::expand_path`]). Other patterns are matched
+/// against the path relative to the target root, and patterns without a `/` also match
+/// any file or directory name, like in `.gitignore`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ExcludePattern {
+    raw: String,
+    pattern: Pattern,
+    absolute: bool,
+    name_only: bool,
+}
+
+impl ExcludePattern {
+    pub fn is_match(&self, path: &Path, root: &Path) -> bool {
+        if self.absolute {
+            return self.pattern.matches_path_with(path, MATCH_OPTIONS);
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        if self.pattern.matches_path_with(relative, MATCH_OPTIONS) {
+            return true;
+        }
+        self.name_only
+            && path
+                .file_name()
+                .is_some_and(|name| self.pattern.matches_with(&name.to_string_lossy(), MATCH_OPTIONS))
+    }
+}
+
+impl TryFrom<String> for ExcludePattern {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        let expanded = scanner::expand_path(&raw).to_string_lossy().into_owned();
+        let absolute = expanded.starts_with('/');
+        let pattern = Pattern::new(expanded.trim_end_matches('/'))
+            .map_err(|e| format!("invalid exclude pattern '{}': {}", raw, e))?;
+        let name_only = !absolute && !raw.contains('/');
+
+        Ok(ExcludePattern { raw, pattern, absolute, name_only })
+    }
+}
+
+impl From<ExcludePattern> for String {
+    fn from(pattern: ExcludePattern) -> String {
+        pattern.raw
+    }
+}
+
+/// Per-rule conditions deciding which files below a target are cleaned.
+/// An empty filter matches everything, so the whole target is removed.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    pub older_than: Option<Duration>,
+    pub exclude: Vec<ExcludePattern>,
+}
+
+impl FileFilter {
+    pub fn is_empty(&self) -> bool {
+        self.older_than.is_none() && self.exclude.is_empty()
+    }
+
+    /// Whether `path` (below `root`) is excluded, along with everything beneath it.
+    pub fn is_excluded(&self, path: &Path, root: &Path) -> bool {
+        self.exclude.iter().any(|p| p.is_match(path, root))
+    }
+
+    /// Checks a single file against the filter. `now` is passed in so a whole
+    /// walk is evaluated against the same point in time.
+    pub fn matches(&self, metadata: &Metadata, now: SystemTime) -> bool {
+        if let Some(age) = self.older_than {
+            let modified = metadata.modified().unwrap_or(now);
+            // Directory atimes are bumped by our own scan, so only trust mtime for them.
+            let accessed = match metadata.is_dir() {
+                true => modified,
+                false => metadata.accessed().unwrap_or(modified),
+            };
+            let last_used = modified.max(accessed);
+            match now.duration_since(last_used) {
+                Ok(elapsed) if elapsed >= age => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, FileTimes};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Writes a file last modified and accessed `age` ago.
+    fn write_aged(path: &Path, age: Duration) {
+        fs::write(path, b"data").unwrap();
+        let time = SystemTime::now() - age;
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_times(FileTimes::new().set_accessed(time).set_modified(time)).unwrap();
+    }
+
+    #[test]
+    fn older_than_matches_only_stale_files() {
+        let dir = std::env::temp_dir().join(format!("tidytui-filter-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        write_aged(&dir.join("old"), 60 * DAY);
+        write_aged(&dir.join("new"), DAY);
+        let old = fs::metadata(dir.join("old")).unwrap();
+        let new = fs::metadata(dir.join("new")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let filter = FileFilter { older_than: Some(30 * DAY), exclude: Vec::new() };
+        let now = SystemTime::now();
+        assert!(filter.matches(&old, now));
+        assert!(!filter.matches(&new, now));
+        assert!(FileFilter::default().matches(&new, now));
+    }
+
+    #[test]
+    fn recent_access_keeps_a_file() {
+        let dir = std::env::temp_dir().join(format!("tidytui-filter-atime-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("used");
+        write_aged(&path, 60 * DAY);
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_times(FileTimes::new().set_accessed(SystemTime::now())).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let filter = FileFilter { older_than: Some(30 * DAY), exclude: Vec::new() };
+        assert!(!filter.matches(&metadata, SystemTime::now()));
+    }
+}
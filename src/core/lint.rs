@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use crate::core::discovery::System;
+use crate::core::registry::{self, Definitions, Rule};
+use crate::core::scanner;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The rule is ignored or the definitions are rejected when loaded.
+    Error,
+    /// Probably a mistake, but loads fine.
+    Warning,
+    /// Worth knowing, e.g. rules for other systems.
+    Note,
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Checks parsed definitions for mistakes that parsing alone does not catch.
+pub fn lint(definitions: &Definitions, system: &System) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut report = |severity, message: String| findings.push(Finding { severity, message });
+
+    let protected = definitions.protected_paths();
+    let mut seen = HashSet::new();
+
+    for group in &definitions.groups {
+        if !seen.insert(&group.id) {
+            report(Severity::Error, format!("group '{}' is defined more than once", group.id));
+        }
+        if group.enabled && group.name.is_empty() {
+            report(Severity::Error, format!("group '{}' has no name", group.id));
+        }
+
+        for rule in &group.rules {
+            let label = format!("group '{}', rule '{}'", group.id, rule_label(rule));
+
+            if rule.path.is_none() && rule.command.is_none() {
+                report(Severity::Error, format!("group '{}' has a rule with neither a path nor a command", group.id));
+            }
+
+            let oses = rule.os.values();
+            let unknown: Vec<_> = oses.iter().filter(|os| !registry::OS_IDS.contains(&os.as_str())).collect();
+            if oses.is_empty() {
+                report(Severity::Error, format!("{}: empty os list, never matches", label));
+            } else if !unknown.is_empty() {
+                for os in unknown {
+                    report(
+                        Severity::Error,
+                        format!("{}: unknown os '{}', expected one of {}", label, os, registry::OS_IDS.join(", ")),
+                    );
+                }
+            } else if !rule.os.matches(&system.os) {
+                report(Severity::Note, format!("{}: never matches this system (os '{}')", label, oses.join(", ")));
+            } else if let Some(program) = rule.requires.iter().find(|p| !system.tools.has(p)) {
+                report(Severity::Note, format!("{}: requires '{}', which is not installed", label, program));
+            } else if let Some(condition) = rule.when.iter().find(|c| !c.holds(&system.environment)) {
+                report(
+                    Severity::Note,
+                    format!("{}: never matches this system (when '{}')", label, String::from(*condition)),
+                );
+            }
+
+            if rule.when_sibling.as_deref().is_some_and(|sibling| sibling.contains('/')) {
+                report(Severity::Error, format!("{}: when_sibling must be a file name, not a path", label));
+            }
+            if rule.command.is_some() && (rule.when_sibling.is_some() || rule.unmodified_for.is_some()) {
+                report(Severity::Warning, format!("{}: when_sibling and unmodified_for are ignored for commands", label));
+            }
+
+            if let Some(path) = &rule.path {
+                if !scanner::expand_path(path).is_absolute() {
+                    report(
+                        Severity::Warning,
+                        format!("{}: relative path, resolved against the current directory", label),
+                    );
+                }
+                if rule.command.is_none() {
+                    if let Err(e) = protected.check_rule(path) {
+                        report(Severity::Error, format!("{}: {:#}", label, e));
+                    }
+                }
+            }
+        }
+    }
+
+    findings
+}
+
+/// Identifies a rule in messages by its command or path.
+fn rule_label(rule: &Rule) -> &str {
+    rule.command.as_deref().or(rule.path.as_deref()).unwrap_or_default()
+}
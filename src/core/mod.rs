@@ -1,23 +1,116 @@
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::core::filter::FileFilter;
 
 pub mod discovery;
 pub mod registry;
 pub mod scanner;
 pub mod cleaner;
+pub mod filter;
+pub mod lint;
+pub mod projects;
+pub mod protected;
+pub mod quarantine;
+pub mod trash;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ItemStatus {
     Scanned,
     Deleted,
+    /// A command action succeeded; holds its captured output.
+    Executed(String),
+    Skipped(String),
     Failed(String),
 }
 
-#[derive(Debug, Clone)]
+impl ItemStatus {
+    /// Whether the item was cleaned, either by removing files or by running its command.
+    pub fn is_cleaned(&self) -> bool {
+        matches!(self, ItemStatus::Deleted | ItemStatus::Executed(_))
+    }
+}
+
+/// How selected items are cleaned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    /// Only report what would be removed.
+    DryRun,
+    /// Move items into the freedesktop Trash so they can be restored.
+    Trash,
+    /// Move items into TidyTUI's quarantine area so the run can be undone.
+    Quarantine,
+    /// Permanently delete items.
+    Delete,
+}
+
+/// What happens to a target path when it is cleaned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanMode {
+    /// Remove everything inside the directory but keep the directory itself,
+    /// preserving its owner and permissions. Files are removed as usual.
+    #[default]
+    Contents,
+    /// Remove the path itself.
+    Whole,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct CleanupItem {
+    pub group_id: String,
     pub name: String,
     pub category: String,
+    /// The cleaned path, or for command items the path used to estimate the size
+    /// (empty when there is none).
     pub path: PathBuf,
+    /// Shell command run instead of deleting `path`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Apparent size of the files. A hardlinked file is counted once per scan, for
+    /// the first item it was found in.
     pub size_bytes: u64,
+    /// Space the files take up on disk.
+    pub allocated_bytes: u64,
+    /// When the most recently changed file below `path` was modified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<SystemTime>,
+    #[serde(skip)]
     pub selected: bool,
     pub status: ItemStatus,
+    pub mode: CleanMode,
+    #[serde(skip)]
+    pub filter: FileFilter,
+    #[serde(skip)]
+    pub cross_mounts: bool,
+    /// Declared root of the rule the item came from, which it must never lie outside of.
+    #[serde(skip)]
+    pub root: PathBuf,
+    /// Where `path` points when it is itself a symlink, or where it really lies
+    /// when a glob reached it through a symlinked directory. Symlinks are never
+    /// followed, so such items are shown but not scanned or cleaned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<PathBuf>,
+}
+
+impl CleanupItem {
+    /// What the item cleans, for display: its path, or its command.
+    pub fn location(&self) -> String {
+        match (&self.command, &self.symlink_target) {
+            (Some(command), _) => format!("$ {}", command),
+            (None, Some(target)) => format!("{} -> {}", self.path.display(), target.display()),
+            (None, None) => self.path.display().to_string(),
+        }
+    }
+
+    /// How long ago the item last changed, for display, e.g. `3d ago`.
+    pub fn age(&self) -> Option<String> {
+        let age = self.modified?.elapsed().unwrap_or(Duration::ZERO).as_secs();
+        let (value, unit) = [(7 * 24 * 3600, "w"), (24 * 3600, "d"), (3600, "h"), (60, "m")]
+            .into_iter()
+            .find(|(secs, _)| age >= *secs)
+            .map_or((age, "s"), |(secs, unit)| (age / secs, unit));
+        Some(format!("{}{} ago", value, unit))
+    }
 }
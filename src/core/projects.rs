@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use crate::core::CleanMode;
+use crate::core::filter::{ExcludePattern, FileFilter};
+use crate::core::registry::Target;
+
+/// What tells a directory apart from an ordinary one of the same name.
+enum Marker {
+    /// One of these files lies next to the directory, e.g. `Cargo.toml` next to `target/`.
+    Sibling(&'static [&'static str]),
+    /// One of these files lies inside the directory.
+    Inside(&'static [&'static str]),
+    /// The name alone is enough.
+    Always,
+}
+
+/// A kind of build artifact that can be regenerated from its project.
+pub struct ArtifactKind {
+    /// Group id of the items found, e.g. for `clean --projects ~/src --group rust_target`.
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Directory name, or `None` for any name.
+    dir: Option<&'static str>,
+    marker: Marker,
+}
+
+const GRADLE_FILES: &[&str] = &["build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"];
+
+/// Checked in order, the first match wins.
+pub const ARTIFACT_KINDS: &[ArtifactKind] = &[
+    ArtifactKind { id: "rust_target", name: "Rust target/", dir: Some("target"), marker: Marker::Sibling(&["Cargo.toml"]) },
+    ArtifactKind { id: "node_modules", name: "node_modules/", dir: Some("node_modules"), marker: Marker::Sibling(&["package.json"]) },
+    ArtifactKind { id: "next_build", name: "Next.js .next/", dir: Some(".next"), marker: Marker::Sibling(&["package.json"]) },
+    ArtifactKind { id: "dist", name: "dist/", dir: Some("dist"), marker: Marker::Sibling(&["package.json", "pyproject.toml", "setup.py"]) },
+    ArtifactKind { id: "python_cache", name: "__pycache__/", dir: Some("__pycache__"), marker: Marker::Always },
+    ArtifactKind { id: "python_venv", name: "Python .venv/", dir: Some(".venv"), marker: Marker::Inside(&["pyvenv.cfg"]) },
+    ArtifactKind { id: "gradle_cache", name: "Gradle .gradle/", dir: Some(".gradle"), marker: Marker::Sibling(GRADLE_FILES) },
+    ArtifactKind { id: "gradle_build", name: "Gradle build/", dir: Some("build"), marker: Marker::Sibling(GRADLE_FILES) },
+    ArtifactKind { id: "cmake_build", name: "CMake build dir", dir: None, marker: Marker::Inside(&["CMakeCache.txt"]) },
+];
+
+/// Files marking the root of a project, used to group artifacts nested deeper
+/// inside it, like `__pycache__` directories.
+const PROJECT_MARKERS: &[&str] = &[
+    ".git", "Cargo.toml", "package.json", "pyproject.toml", "setup.py", "build.gradle", "build.gradle.kts",
+    "settings.gradle", "settings.gradle.kts", "CMakeLists.txt", "go.mod",
+];
+
+impl ArtifactKind {
+    fn matches(&self, dir: &Path) -> bool {
+        if self.dir.is_some_and(|name| dir.file_name().is_none_or(|n| n != name)) {
+            return false;
+        }
+        match self.marker {
+            Marker::Sibling(files) => dir.parent().is_some_and(|parent| files.iter().any(|f| parent.join(f).exists())),
+            Marker::Inside(files) => files.iter().any(|f| dir.join(f).exists()),
+            Marker::Always => true,
+        }
+    }
+}
+
+/// Walks `roots` for build artifacts and returns a target removing each one,
+/// categorized by the project it belongs to. Artifacts are not descended into,
+/// so nested `node_modules` are part of the outermost one. Symlinks are not
+/// followed, and other filesystems are not entered.
+pub fn find_artifacts(roots: &[PathBuf], exclude: &[ExcludePattern]) -> Vec<Target> {
+    let mut targets = Vec::new();
+    for root in roots {
+        let root = &std::path::absolute(root).unwrap_or_else(|_| root.clone());
+        let mut walker = WalkDir::new(root).same_file_system(true).into_iter();
+        while let Some(entry) = walker.next() {
+            let Ok(entry) = entry else { continue };
+            if !entry.file_type().is_dir() || entry.depth() == 0 {
+                continue;
+            }
+            let path = entry.path();
+            if path.file_name().is_some_and(|n| n == ".git") {
+                walker.skip_current_dir();
+                continue;
+            }
+            let Some(kind) = ARTIFACT_KINDS.iter().find(|kind| kind.matches(path)) else {
+                continue;
+            };
+            walker.skip_current_dir();
+
+            targets.push(Target {
+                group_id: kind.id.to_string(),
+                name: kind.name.to_string(),
+                category: display_path(&project_root(path, root)),
+                path: None,
+                literal_path: Some(path.to_path_buf()),
+                command: None,
+                size_command: None,
+                mode: CleanMode::Whole,
+                filter: FileFilter { older_than: None, exclude: exclude.to_vec() },
+                cross_mounts: false,
+                when_sibling: None,
+                unmodified_for: None,
+            });
+        }
+    }
+    targets
+}
+
+/// The nearest directory above `artifact` holding a project marker, without
+/// going above `root`. Falls back to the artifact's parent directory.
+fn project_root(artifact: &Path, root: &Path) -> PathBuf {
+    let parent = artifact.parent().unwrap_or(artifact);
+    parent
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| PROJECT_MARKERS.iter().any(|marker| dir.join(marker).exists()))
+        .unwrap_or(parent)
+        .to_path_buf()
+}
+
+/// `path` with the home directory shortened to `~`.
+fn display_path(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
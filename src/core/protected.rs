@@ -0,0 +1,251 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{bail, Result};
+use crate::core::{discovery, scanner};
+
+/// System directories that are never cleaned, nor anything inside them.
+const PROTECTED_TREES: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/lib", "/lib32", "/lib64", "/proc", "/sbin", "/sys", "/usr",
+    "~/.ssh", "~/.gnupg",
+];
+
+/// Directories holding cleanable paths that must never be cleaned themselves.
+const PROTECTED_DIRS: &[&str] = &[
+    "/", "/home", "/media", "/mnt", "/opt", "/root", "/run", "/srv", "/var", "/var/lib",
+    "~", "~/.config", "~/.local", "~/.local/share",
+    "$XDG_CONFIG_HOME", "$XDG_DATA_HOME", "$XDG_STATE_HOME",
+];
+
+/// Paths the cleaner refuses to touch, whatever the definitions say: the built-in
+/// lists above plus the `protected` paths of the definitions.
+#[derive(Debug, Clone)]
+pub struct ProtectedPaths {
+    /// Protected along with everything below them.
+    trees: Vec<PathBuf>,
+    /// Protected themselves, so neither they nor a parent of theirs may be cleaned.
+    dirs: Vec<PathBuf>,
+}
+
+impl ProtectedPaths {
+    /// The built-in lists plus `extra` paths, which are protected along with everything below them.
+    pub fn new(extra: &[String]) -> Self {
+        ProtectedPaths {
+            trees: expand(PROTECTED_TREES.iter().copied().chain(extra.iter().map(String::as_str))),
+            dirs: expand(PROTECTED_DIRS.iter().copied()),
+        }
+    }
+
+    /// Fails if `path` may not be cleaned: when it is, contains or lies inside a
+    /// protected path, or lies outside `root`, the declared root of the rule it
+    /// came from. When `removes_path` is set, i.e. `path` itself would be removed
+    /// rather than only files below it, it must not be a mount point either.
+    pub fn check(&self, path: &Path, root: &Path, removes_path: bool) -> Result<()> {
+        self.check_location(path, root)?;
+        if removes_path && discovery::is_mount_point(&normalize(path)) {
+            bail!("{} is a mount point", path.display());
+        }
+        Ok(())
+    }
+
+    /// [`Self::check`] without looking at what is currently mounted, which may
+    /// differ between machines sharing the same definitions.
+    fn check_location(&self, path: &Path, root: &Path) -> Result<()> {
+        let normalized = normalize(path);
+        if !normalized.starts_with(normalize(root)) {
+            bail!("{} lies outside its rule root {}", path.display(), root.display());
+        }
+
+        // Check where the path really is too, in case a parent is a symlink.
+        let real = resolve_parent(&normalized);
+        if let Some(real) = &real {
+            let root = normalize(root);
+            let real_roots = [resolve_parent(&root), fs::canonicalize(&root).ok()];
+            if !real.starts_with(&root) && !real_roots.iter().flatten().any(|r| real.starts_with(r)) {
+                bail!("{} lies outside its rule root {} (really at {})", path.display(), root.display(), real.display());
+            }
+        }
+        for candidate in std::iter::once(&normalized).chain(real.as_ref()) {
+            let tree = self.trees.iter().find(|t| candidate.starts_with(t) || t.starts_with(candidate));
+            let dir = self.dirs.iter().find(|d| d.starts_with(candidate));
+            if let Some(protected) = dir.or(tree) {
+                if candidate == protected {
+                    bail!("{} is a protected path", path.display());
+                } else if candidate.starts_with(protected) {
+                    bail!("{} is inside protected path {}", path.display(), protected.display());
+                } else {
+                    bail!("{} contains protected path {}", path.display(), protected.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if a rule's path points at a protected location. A glob rule only
+    /// cleans its matches, so it is refused when its root lies inside a protected
+    /// tree, or when it matches every entry right below a protected directory like `~/*`.
+    pub fn check_rule(&self, path_str: &str) -> Result<()> {
+        let root = declared_root(path_str);
+        if !has_glob(path_str) {
+            return self.check_location(&scanner::expand_path(path_str), &root);
+        }
+
+        let normalized = normalize(&root);
+        if let Some(tree) = self.trees.iter().find(|t| normalized.starts_with(t)) {
+            bail!("{} is inside protected path {}", path_str, tree.display());
+        }
+        let below_root = scanner::expand_path(path_str).components().count() - root.components().count();
+        if below_root == 1 && self.dirs.contains(&normalized) {
+            bail!("{} matches everything in protected path {}", path_str, root.display());
+        }
+        Ok(())
+    }
+}
+
+/// Expands and normalizes protected paths, adding where they really live when a
+/// parent is a symlink (e.g. `/home` pointing to `/var/home`).
+fn expand<'a>(paths: impl Iterator<Item = &'a str>) -> Vec<PathBuf> {
+    paths
+        .flat_map(|p| {
+            let path = normalize(&scanner::expand_path(p));
+            let real = fs::canonicalize(&path).ok().filter(|real| *real != path);
+            std::iter::once(path).chain(real)
+        })
+        .collect()
+}
+
+/// The part of a rule path before any glob pattern or `..`. Every path the rule
+/// yields must stay below it.
+pub fn declared_root(path_str: &str) -> PathBuf {
+    scanner::expand_path(path_str)
+        .components()
+        .take_while(|c| *c != Component::ParentDir && !has_glob(&c.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+fn has_glob(path_str: &str) -> bool {
+    path_str.contains(['*', '?', '['])
+}
+
+/// Makes `path` absolute and resolves `.` and `..` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `path` with symlinks in its parent directories resolved, leaving the last
+/// component alone. `None` if that changes nothing or the parent does not exist.
+fn resolve_parent(path: &Path) -> Option<PathBuf> {
+    let real = fs::canonicalize(path.parent()?).ok()?.join(path.file_name()?);
+    (real != path).then_some(real)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn protected() -> ProtectedPaths {
+        ProtectedPaths::new(&[])
+    }
+
+    /// A fresh directory below the system temp dir, removed by the caller.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tidytui-protected-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rejects_home_directory() {
+        assert!(protected().check_rule("~/").is_err());
+        assert!(protected().check_rule("~").is_err());
+    }
+
+    #[test]
+    fn rejects_parent_of_home() {
+        assert!(protected().check_rule("~/..").is_err());
+    }
+
+    #[test]
+    fn rejects_glob_over_home() {
+        let err = protected().check_rule("~/*").unwrap_err();
+        assert!(err.to_string().contains("matches everything"), "{}", err);
+    }
+
+    #[test]
+    fn allows_glob_below_home_subdirectory() {
+        assert!(protected().check_rule("~/.cache/JetBrains/*/caches").is_ok());
+    }
+
+    #[test]
+    fn rejects_system_trees() {
+        assert!(protected().check_rule("/usr/x").is_err());
+        assert!(protected().check_rule("/usr/*/cache").is_err());
+        assert!(protected().check(Path::new("/usr/x"), Path::new("/usr/x"), false).is_err());
+    }
+
+    #[test]
+    fn allows_cache_directories() {
+        assert!(protected().check_rule("~/.cache/pip").is_ok());
+        assert!(protected().check_rule("/var/cache/apt/archives/").is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_outside_root() {
+        let root = Path::new("/var/tmp/tidytui-root");
+        assert!(protected().check(Path::new("/var/tmp/tidytui-root/../other"), root, false).is_err());
+    }
+
+    #[test]
+    fn extra_paths_are_protected_with_everything_below() {
+        let protected = ProtectedPaths::new(&["/var/tmp/tidytui-keep".to_string()]);
+        assert!(protected.check_rule("/var/tmp/tidytui-keep/sub").is_err());
+        assert!(protected.check_rule("/var/tmp/tidytui-other").is_ok());
+    }
+
+    #[test]
+    fn rejects_path_behind_symlinked_parent() {
+        let dir = temp_dir("symlink");
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("real/caches")).unwrap();
+        fs::create_dir_all(dir.join("outside/caches")).unwrap();
+        symlink(dir.join("outside"), root.join("link")).unwrap();
+
+        let inside = protected().check(&root.join("real/caches"), &root, false);
+        let escaped = protected().check(&root.join("link/caches"), &root, false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(inside.is_ok(), "{:?}", inside);
+        let err = escaped.unwrap_err();
+        assert!(err.to_string().contains("outside its rule root"), "{}", err);
+    }
+
+    #[test]
+    fn allows_symlinked_root() {
+        let dir = temp_dir("symlinked-root");
+        fs::create_dir_all(dir.join("real/caches")).unwrap();
+        symlink(dir.join("real"), dir.join("root")).unwrap();
+
+        let result = protected().check(&dir.join("root/caches"), &dir.join("root"), false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[test]
+    fn declared_root_stops_at_glob_and_parent() {
+        assert_eq!(declared_root("/var/cache/*/pkg"), PathBuf::from("/var/cache"));
+        assert_eq!(declared_root("/var/cache/../lib"), PathBuf::from("/var/cache"));
+    }
+}
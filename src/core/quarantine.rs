@@ -0,0 +1,219 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::core::trash;
+
+/// How long quarantined runs are kept when `quarantine_retention` is not configured.
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const MANIFEST: &str = "manifest.jsonl";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    name: String,
+    original: PathBuf,
+}
+
+/// Result of restoring a quarantined run.
+#[derive(Debug, Default)]
+pub struct UndoReport {
+    pub run_id: String,
+    pub restored: usize,
+    /// Entries left in quarantine, with the reason they could not be restored.
+    pub conflicts: Vec<(PathBuf, String)>,
+}
+
+/// `$XDG_STATE_HOME/tidytui/quarantine`, holding one directory per cleaning run.
+pub fn quarantine_root() -> Option<PathBuf> {
+    dirs::state_dir().map(|d| d.join("tidytui").join("quarantine"))
+}
+
+/// A single cleaning run whose removed items are renamed into
+/// `<quarantine root>/<run-id>` instead of being deleted.
+#[derive(Debug)]
+pub struct QuarantineRun {
+    pub id: String,
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl QuarantineRun {
+    /// Creates the directory of a new run, named after the current time. Runs
+    /// started within the same second get a `.2`, `.3`, ... suffix.
+    pub fn new() -> Result<Self> {
+        let root = quarantine_root().context("Could not determine the state directory")?;
+        fs::create_dir_all(&root).context("Failed to create quarantine directory")?;
+        let base_id = trash::local_timestamp().replace(':', "-");
+
+        let mut id = base_id.clone();
+        let mut counter = 1;
+        // Created exclusively, so two runs started at once never share a directory.
+        loop {
+            match fs::create_dir(root.join(&id)) {
+                Ok(()) => break,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    counter += 1;
+                    id = format!("{}.{}", base_id, counter);
+                }
+                Err(e) => return Err(e).context("Failed to create quarantine directory"),
+            }
+        }
+
+        Ok(QuarantineRun { dir: root.join(&id), id, next: AtomicUsize::new(0) })
+    }
+
+    /// Renames `path` into this run. Only works within one filesystem, so items
+    /// elsewhere are rejected rather than copied.
+    pub fn move_in(&self, path: &Path) -> Result<()> {
+        let path = std::path::absolute(path).context("Failed to resolve path")?;
+
+        let device = fs::symlink_metadata(&path).context("Failed to read item metadata")?.dev();
+        if fs::metadata(&self.dir)?.dev() != device {
+            bail!("not on the same filesystem as the quarantine area");
+        }
+
+        let name = self.next.fetch_add(1, Ordering::SeqCst).to_string();
+        let destination = self.dir.join(&name);
+        fs::rename(&path, &destination).context("Failed to move item to quarantine")?;
+
+        let entry = ManifestEntry { name, original: path.clone() };
+        let recorded = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(MANIFEST))
+            .and_then(|mut f| writeln!(f, "{}", serde_json::to_string(&entry)?));
+        if let Err(e) = recorded {
+            // Without a manifest entry the item could never be restored, so put it back.
+            let _ = fs::rename(&destination, &path);
+            return Err(e).context("Failed to record quarantined item");
+        }
+
+        Ok(())
+    }
+}
+
+/// Quarantined run ids, oldest first.
+pub fn list_runs() -> Result<Vec<String>> {
+    let Some(root) = quarantine_root() else {
+        return Ok(Vec::new());
+    };
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut runs: Vec<String> = fs::read_dir(&root)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().join(MANIFEST).exists())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    runs.sort_by(|a, b| run_order(a).cmp(&run_order(b)));
+    Ok(runs)
+}
+
+/// Sort key of a run id, so that `<time>.10` comes after `<time>.9`.
+fn run_order(id: &str) -> (&str, u32) {
+    match id.split_once('.') {
+        Some((time, counter)) => (time, counter.parse().unwrap_or(0)),
+        None => (id, 1),
+    }
+}
+
+/// Restores every item of `run_id` (or of the latest run) to its original path.
+/// Items whose original path has been recreated in the meantime stay quarantined.
+pub fn undo(run_id: Option<&str>) -> Result<UndoReport> {
+    let root = quarantine_root().context("Could not determine the state directory")?;
+    let run_id = match run_id {
+        Some(id) => id.to_string(),
+        None => list_runs()?.pop().context("No quarantined runs to undo")?,
+    };
+    let dir = root.join(&run_id);
+    let manifest = fs::read_to_string(dir.join(MANIFEST))
+        .with_context(|| format!("Quarantined run '{}' not found", run_id))?;
+
+    let mut report = UndoReport { run_id, ..Default::default() };
+    let mut remaining = Vec::new();
+    for line in manifest.lines().filter(|l| !l.trim().is_empty()) {
+        let entry: ManifestEntry = serde_json::from_str(line).context("Corrupt quarantine manifest")?;
+        match restore_entry(&dir, &entry) {
+            Ok(()) => report.restored += 1,
+            Err(e) => {
+                report.conflicts.push((entry.original.clone(), format!("{:#}", e)));
+                remaining.push(line);
+            }
+        }
+    }
+
+    if remaining.is_empty() {
+        fs::remove_dir_all(&dir).context("Failed to remove restored run")?;
+    } else {
+        let contents: String = remaining.iter().map(|l| format!("{}\n", l)).collect();
+        fs::write(dir.join(MANIFEST), contents).context("Failed to update quarantine manifest")?;
+    }
+
+    Ok(report)
+}
+
+fn restore_entry(dir: &Path, entry: &ManifestEntry) -> Result<()> {
+    if fs::symlink_metadata(&entry.original).is_ok() {
+        bail!("original path already exists");
+    }
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent).context("Failed to recreate parent directory")?;
+    }
+    fs::rename(dir.join(&entry.name), &entry.original).context("Failed to restore item")
+}
+
+/// Permanently deletes quarantined runs older than `retention`.
+pub fn purge_expired(retention: Duration) -> Result<()> {
+    let Some(root) = quarantine_root() else {
+        return Ok(());
+    };
+    if !root.exists() {
+        return Ok(());
+    }
+
+    let now = SystemTime::now();
+    for entry in fs::read_dir(&root)?.filter_map(|e| e.ok()) {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() > retention);
+        if expired {
+            fs::remove_dir_all(entry.path())
+                .with_context(|| format!("Failed to purge {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_of_the_same_second_sort_by_counter() {
+        let mut runs = vec!["2026-01-02T10-00-00.10", "2026-01-02T10-00-00.2", "2026-01-02T10-00-01", "2026-01-02T10-00-00"];
+        runs.sort_by(|a, b| run_order(a).cmp(&run_order(b)));
+        assert_eq!(runs, ["2026-01-02T10-00-00", "2026-01-02T10-00-00.2", "2026-01-02T10-00-00.10", "2026-01-02T10-00-01"]);
+    }
+
+    // The only test changing XDG_STATE_HOME, as tests share the environment.
+    #[test]
+    fn runs_started_together_get_their_own_directory() {
+        let state = std::env::temp_dir().join(format!("tidytui-quarantine-{}", std::process::id()));
+        std::env::set_var("XDG_STATE_HOME", &state);
+        let runs: Vec<_> = (0..3).map(|_| QuarantineRun::new().unwrap()).collect();
+        let created = runs.iter().all(|run| run.dir.is_dir());
+        fs::remove_dir_all(&state).unwrap();
+
+        assert!(created);
+        assert_ne!(runs[0].id, runs[1].id);
+        assert_ne!(runs[1].id, runs[2].id);
+        assert_ne!(runs[0].id, runs[2].id);
+    }
+}
@@ -1,67 +1,577 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
-use anyhow::Result;
-use crate::core::discovery::OsType;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use crate::core::{quarantine, CleanMode};
+use crate::core::discovery::{Environment, Fact, OsType, System};
+use crate::core::filter::{ExcludePattern, FileFilter};
+use crate::core::protected::ProtectedPaths;
+
+/// A minimum file age written as a number and a unit, e.g. `30d`, `12h` or `2w`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Age(pub Duration);
+
+impl FromStr for Age {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let invalid = || format!("invalid age '{}', expected e.g. '30d'", s);
+        let value: u64 = value.parse().map_err(|_| invalid())?;
+        let multiplier = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(format!("invalid age unit in '{}', expected one of s, m, h, d, w", s)),
+        };
+        let secs = value.checked_mul(multiplier).ok_or_else(invalid)?;
+        Ok(Age(Duration::from_secs(secs)))
+    }
+}
+
+impl TryFrom<String> for Age {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Age> for String {
+    fn from(age: Age) -> String {
+        format!("{}s", age.0.as_secs())
+    }
+}
+
+/// An environment fact a rule only applies under, or with a leading `!` only
+/// applies without, e.g. `container` or `!immutable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    pub fact: Fact,
+    pub negated: bool,
+}
+
+impl Condition {
+    pub fn holds(&self, environment: &Environment) -> bool {
+        environment.has(self.fact) != self.negated
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negated, name) = match s.trim().strip_prefix('!') {
+            Some(name) => (true, name),
+            None => (false, s.trim()),
+        };
+        let names: Vec<_> = Fact::ALL.iter().map(|(name, _)| *name).collect();
+        let fact = Fact::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, fact)| *fact)
+            .ok_or_else(|| format!("unknown condition '{}', expected one of {} (negated with '!')", s, names.join(", ")))?;
+        Ok(Condition { fact, negated })
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> String {
+        let prefix = if condition.negated { "!" } else { "" };
+        format!("{}{}", prefix, condition.fact.name())
+    }
+}
+
+/// The systems a rule is written for: a single `os` value or a list of them.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OsList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OsList {
+    pub fn values(&self) -> &[String] {
+        match self {
+            OsList::One(os) => std::slice::from_ref(os),
+            OsList::Many(list) => list,
+        }
+    }
+
+    /// Whether any of the values matches `os_type`.
+    pub fn matches(&self, os_type: &OsType) -> bool {
+        let ids = os_ids(os_type);
+        self.values().iter().any(|os| ids.contains(&os.as_str()))
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Rule {
-    pub os: String,
-    pub path: String,
+    pub os: OsList,
+    /// Programs that must be installed for the rule to apply, e.g. `[flatpak]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    /// Environment facts that must hold for the rule to apply, e.g. `[container]` or `["!immutable"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub when: Vec<Condition>,
+    /// Path to clean. For command rules it is only used to estimate the size.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Shell command that cleans this rule instead of deleting `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Shell command printing how much `command` would free, e.g. `journalctl --disk-usage`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_command: Option<String>,
+    /// Only files whose last modification and access are older than this are cleaned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub older_than: Option<Age>,
+    /// Only paths with a file of this name next to them are cleaned, e.g. `Cargo.toml`
+    /// for `target` directories. May be a glob pattern like `build.gradle*`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when_sibling: Option<String>,
+    /// Only paths in which nothing was modified for this long are cleaned, e.g. the
+    /// build output of projects nobody has worked on lately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unmodified_for: Option<Age>,
+    /// Whether to keep the directory itself (`contents`, default) or remove it (`whole`).
+    #[serde(default)]
+    pub mode: CleanMode,
+    /// Paths below this rule that must never be scanned or cleaned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<ExcludePattern>,
+    /// Descend into other filesystems mounted below the path. Off by default so a
+    /// bind mount inside a cache directory is never scanned or cleaned.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cross_mounts: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Group {
     pub id: String,
+    #[serde(default)]
     pub name: String,
     pub description: Option<String>,
+    /// Set to `false` in a later definitions file to drop a group defined earlier.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
     pub rules: Vec<Rule>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+fn enabled_by_default() -> bool {
+    true
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Definitions {
+    /// How long quarantined runs are kept before being deleted for good.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine_retention: Option<Age>,
+    /// Exclude patterns applied to every rule.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<ExcludePattern>,
+    /// Paths that must never be cleaned, nor anything inside them, on top of the built-in ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected: Vec<String>,
+    #[serde(default)]
     pub groups: Vec<Group>,
 }
 
-pub fn load_definitions() -> Result<Definitions> {
+impl Definitions {
+    pub fn quarantine_retention(&self) -> Duration {
+        self.quarantine_retention.map(|age| age.0).unwrap_or(quarantine::DEFAULT_RETENTION)
+    }
+
+    pub fn protected_paths(&self) -> ProtectedPaths {
+        ProtectedPaths::new(&self.protected)
+    }
+
+    /// Layers `other` on top of these definitions. Its groups replace groups with
+    /// the same id or are appended, and disabled ones remove them. Its settings
+    /// win, and its exclude and protected paths are added to the existing ones.
+    pub fn merge(&mut self, other: Definitions) {
+        if other.quarantine_retention.is_some() {
+            self.quarantine_retention = other.quarantine_retention;
+        }
+        self.exclude.extend(other.exclude);
+        self.protected.extend(other.protected);
+        for group in other.groups {
+            if !group.enabled {
+                self.groups.retain(|g| g.id != group.id);
+                continue;
+            }
+            match self.groups.iter_mut().find(|g| g.id == group.id) {
+                Some(existing) => *existing = group,
+                None => self.groups.push(group),
+            }
+        }
+    }
+
+    /// Removes every rule that runs a `command` (and with it its `size_command`).
+    fn drop_commands(&mut self) {
+        for group in &mut self.groups {
+            group.rules.retain(|rule| rule.command.is_none());
+        }
+    }
+
+    /// Whether any rule runs a `command`.
+    pub fn has_commands(&self) -> bool {
+        self.groups.iter().flat_map(|g| &g.rules).any(|rule| rule.command.is_some())
+    }
+}
+
+/// A rule path that applies to the current system, ready to be scanned.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub group_id: String,
+    pub name: String,
+    pub category: String,
+    pub path: Option<String>,
+    /// A path found on disk, e.g. a project's build artifact, scanned as it is
+    /// instead of expanding and globbing `path`. It is its own rule root.
+    pub literal_path: Option<PathBuf>,
+    pub command: Option<String>,
+    pub size_command: Option<String>,
+    pub mode: CleanMode,
+    pub filter: FileFilter,
+    pub cross_mounts: bool,
+    pub when_sibling: Option<String>,
+    pub unmodified_for: Option<Duration>,
+}
+
+/// Where definitions are loaded from, lowest precedence first: the system files,
+/// the user's, then the current directory. Directories are drop-in directories
+/// whose `*.yaml` files are loaded in lexical order.
+pub fn search_paths() -> Vec<PathBuf> {
     let mut paths = vec![
-        Path::new("definitions.yaml").to_path_buf(),
         Path::new("/usr/share/tidytui/definitions.yaml").to_path_buf(),
+        Path::new("/usr/share/tidytui/definitions.d").to_path_buf(),
+        Path::new("/etc/tidytui/definitions.d").to_path_buf(),
     ];
 
     if let Some(config_dir) = dirs::config_dir() {
-        paths.insert(1, config_dir.join("tidytui").join("definitions.yaml"));
+        paths.push(config_dir.join("tidytui").join("definitions.yaml"));
+        paths.push(config_dir.join("tidytui").join("definitions.d"));
+    }
+
+    paths.push(Path::new(LOCAL_DEFINITIONS).to_path_buf());
+    paths
+}
+
+/// The definitions file in the current directory. It is loaded without being asked
+/// for, so scanning in a directory someone else can write to must not run its commands.
+const LOCAL_DEFINITIONS: &str = "definitions.yaml";
+
+/// Whether `path`, one of the [`definition_files`], was picked up from the current
+/// directory rather than installed or passed with `--config`. Rules running a
+/// command are ignored in such files.
+pub fn is_untrusted(path: &Path, config: &[PathBuf]) -> bool {
+    path == Path::new(LOCAL_DEFINITIONS) && !config.iter().any(|c| c == path)
+}
+
+/// Reads and parses a definitions file without validating it. Syntax errors
+/// carry the line and column they were found at.
+pub fn parse_definitions(path: &Path) -> Result<Definitions> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_yaml::from_str(&content).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+}
+
+/// Every definitions file to load, lowest precedence first: the files found in the
+/// search paths followed by the `config` files given on the command line.
+pub fn definition_files(config: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in search_paths() {
+        if path.is_dir() {
+            files.extend(drop_in_files(&path)?);
+        } else if path.exists() {
+            files.push(path);
+        }
     }
+    files.extend(config.iter().cloned());
+    Ok(files)
+}
+
+/// The `*.yaml` files of a drop-in directory, in lexical order.
+fn drop_in_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "yaml") && p.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
 
-    // Try to find the first path that exists
-    for path in paths {
-        if path.exists() {
-            let content = fs::read_to_string(&path)?;
-            let definitions: Definitions = serde_yaml::from_str(&content)?;
-            return Ok(definitions);
+/// Loads and merges every definitions file, see [`definition_files`].
+pub fn load_definitions(config: &[PathBuf]) -> Result<Definitions> {
+    let files = definition_files(config)?;
+    if files.is_empty() {
+        let searched: Vec<String> = search_paths().iter().map(|p| p.display().to_string()).collect();
+        return Err(anyhow::anyhow!("No definitions found, searched: {}", searched.join(", ")));
+    }
+
+    let mut definitions = Definitions::default();
+    for path in &files {
+        let mut layer = parse_definitions(path)?;
+        if is_untrusted(path, config) {
+            layer.drop_commands();
         }
+        definitions.merge(layer);
     }
 
-    Err(anyhow::anyhow!("Changes definitions.yaml not found in any of the search paths."))
+    validate(&definitions)?;
+    Ok(definitions)
 }
 
-pub fn filter_rules(definitions: &Definitions, os_type: &OsType) -> Vec<(String, String, String)> {
+fn validate(definitions: &Definitions) -> Result<()> {
+    let protected = definitions.protected_paths();
+    for group in &definitions.groups {
+        if group.name.is_empty() {
+            return Err(anyhow::anyhow!("Group '{}' has no name", group.id));
+        }
+        for rule in &group.rules {
+            if rule.path.is_none() && rule.command.is_none() {
+                return Err(anyhow::anyhow!("Group '{}' has a rule with neither a path nor a command", group.id));
+            }
+            // Command rules only use their path to estimate the size.
+            if let (Some(path), None) = (&rule.path, &rule.command) {
+                protected
+                    .check_rule(path)
+                    .with_context(|| format!("Group '{}' has a rule that would clean a protected path", group.id))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Values a rule's `os` may take: `any`, a distribution, or a family of
+/// distributions like `debian-like`, which also matches Ubuntu and its derivatives.
+pub const OS_IDS: &[&str] = &[
+    "any", "arch", "ubuntu", "debian", "fedora", "opensuse",
+    "nixos", "alpine", "gentoo", "void", "solus", "clear-linux",
+    "arch-like", "debian-like", "fedora-like", "suse-like",
+];
+
+/// The `os` values matching `os_type`.
+pub fn os_ids(os_type: &OsType) -> &'static [&'static str] {
+    match os_type {
+        OsType::Arch => &["any", "arch", "arch-like"],
+        OsType::Ubuntu => &["any", "ubuntu", "debian-like"],
+        OsType::Debian => &["any", "debian", "debian-like"],
+        OsType::Fedora => &["any", "fedora", "fedora-like"],
+        OsType::OpenSuse => &["any", "opensuse", "suse-like"],
+        OsType::NixOs => &["any", "nixos"],
+        OsType::Alpine => &["any", "alpine"],
+        OsType::Gentoo => &["any", "gentoo"],
+        OsType::Void => &["any", "void"],
+        OsType::Solus => &["any", "solus"],
+        OsType::ClearLinux => &["any", "clear-linux"],
+        OsType::Unknown(_) => &["any"],
+    }
+}
+
+impl Rule {
+    /// Whether the rule applies to `system`: its `os` matches, every program it
+    /// requires is installed and all of its `when` conditions hold.
+    pub fn matches(&self, system: &System) -> bool {
+        self.os.matches(&system.os)
+            && self.requires.iter().all(|program| system.tools.has(program))
+            && self.when.iter().all(|condition| condition.holds(&system.environment))
+    }
+}
+
+pub fn filter_rules(definitions: &Definitions, system: &System) -> Vec<Target> {
     let mut cleanable_paths = Vec::new();
-    let os_id = match os_type {
-        OsType::Arch => "arch",
-        OsType::Ubuntu => "ubuntu",
-        OsType::Debian => "debian",
-        OsType::Fedora => "fedora",
-        OsType::OpenSuse => "opensuse",
-        OsType::Unknown(_) => "any", // Default fallback if needed, or handle specifically
-    };
 
     for group in &definitions.groups {
         for rule in &group.rules {
-            if rule.os == os_id || rule.os == "any" {
-                cleanable_paths.push((group.name.clone(), group.name.clone(), rule.path.clone()));
+            if rule.matches(system) {
+                cleanable_paths.push(Target {
+                    group_id: group.id.clone(),
+                    name: group.name.clone(),
+                    category: group.name.clone(),
+                    path: rule.path.clone(),
+                    literal_path: None,
+                    command: rule.command.clone(),
+                    size_command: rule.size_command.clone(),
+                    mode: rule.mode,
+                    filter: FileFilter {
+                        older_than: rule.older_than.map(|age| age.0),
+                        exclude: definitions.exclude.iter().chain(&rule.exclude).cloned().collect(),
+                    },
+                    cross_mounts: rule.cross_mounts,
+                    when_sibling: rule.when_sibling.clone(),
+                    unmodified_for: rule.unmodified_for.map(|age| age.0),
+                });
             }
         }
     }
     cleanable_paths
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(n: u64) -> Duration {
+        Duration::from_secs(n * 24 * 60 * 60)
+    }
+
+    #[test]
+    fn parses_ages_in_every_unit() {
+        assert_eq!("45s".parse::<Age>(), Ok(Age(Duration::from_secs(45))));
+        assert_eq!("10m".parse::<Age>(), Ok(Age(Duration::from_secs(600))));
+        assert_eq!("12h".parse::<Age>(), Ok(Age(Duration::from_secs(12 * 3600))));
+        assert_eq!("30d".parse::<Age>(), Ok(Age(days(30))));
+        assert_eq!("2w".parse::<Age>(), Ok(Age(days(14))));
+    }
+
+    #[test]
+    fn age_ignores_surrounding_whitespace() {
+        assert_eq!(" 7d ".parse::<Age>(), Ok(Age(days(7))));
+    }
+
+    #[test]
+    fn rejects_invalid_ages() {
+        assert!("30".parse::<Age>().is_err());
+        assert!("d".parse::<Age>().is_err());
+        assert!("30y".parse::<Age>().is_err());
+        assert!("-1d".parse::<Age>().is_err());
+        assert!("".parse::<Age>().is_err());
+    }
+
+    #[test]
+    fn rejects_ages_that_overflow() {
+        let err = "99999999999999999w".parse::<Age>().unwrap_err();
+        assert!(err.contains("invalid age"), "{}", err);
+    }
+
+    #[test]
+    fn families_match_derived_distributions() {
+        let debian_like = OsList::One("debian-like".to_string());
+        assert!(debian_like.matches(&OsType::Debian));
+        assert!(debian_like.matches(&OsType::Ubuntu));
+        assert!(!debian_like.matches(&OsType::Arch));
+        assert!(!OsList::One("debian".to_string()).matches(&OsType::Ubuntu));
+    }
+
+    #[test]
+    fn os_lists_match_any_entry() {
+        let list = OsList::Many(vec!["arch-like".to_string(), "fedora".to_string()]);
+        assert!(list.matches(&OsType::Arch));
+        assert!(list.matches(&OsType::Fedora));
+        assert!(!list.matches(&OsType::Debian));
+        assert!(!OsList::Many(Vec::new()).matches(&OsType::Debian));
+    }
+
+    #[test]
+    fn unknown_systems_only_match_any() {
+        let unknown = OsType::Unknown("someos".to_string());
+        assert!(OsList::One("any".to_string()).matches(&unknown));
+        assert!(!OsList::One("debian-like".to_string()).matches(&unknown));
+    }
+
+    #[test]
+    fn only_the_implicit_local_file_is_untrusted() {
+        let local = Path::new("definitions.yaml");
+        assert!(is_untrusted(local, &[]));
+        assert!(!is_untrusted(local, &[local.to_path_buf()]));
+        assert!(!is_untrusted(Path::new("/etc/tidytui/definitions.d/10-local.yaml"), &[]));
+    }
+
+    #[test]
+    fn drop_commands_keeps_path_rules() {
+        let mut definitions: Definitions = serde_yaml::from_str(
+            "groups:\n  - id: logs\n    name: Logs\n    rules:\n      - { os: any, path: /var/tmp/tidytui-logs }\n      - { os: any, command: \"rm -rf ~\", size_command: \"touch /tmp/pwned\" }\n",
+        )
+        .unwrap();
+        assert!(definitions.has_commands());
+        definitions.drop_commands();
+        assert!(!definitions.has_commands());
+        assert_eq!(definitions.groups[0].rules.len(), 1);
+    }
+
+    fn definitions(yaml: &str) -> Definitions {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let mut merged = definitions("quarantine_retention: 14d\ngroups:\n  - { id: pip, name: Pip }\n");
+        merged.merge(definitions("quarantine_retention: 3d\ngroups:\n  - { id: npm, name: npm }\n"));
+        merged.merge(definitions("groups: []\n"));
+
+        assert_eq!(merged.quarantine_retention, "3d".parse().ok());
+        let ids: Vec<_> = merged.groups.iter().map(|g| g.id.as_str()).collect();
+        assert_eq!(ids, ["pip", "npm"]);
+    }
+
+    #[test]
+    fn groups_are_overridden_by_id() {
+        let mut merged = definitions(
+            "groups:\n  - id: pip\n    name: Pip\n    rules:\n      - { os: any, path: ~/.cache/pip }\n  - { id: npm, name: npm }\n",
+        );
+        merged.merge(definitions("groups:\n  - { id: pip, name: My Pip }\n"));
+
+        assert_eq!(merged.groups.len(), 2);
+        assert_eq!(merged.groups[0].name, "My Pip");
+        assert!(merged.groups[0].rules.is_empty());
+    }
+
+    #[test]
+    fn disabled_groups_are_removed() {
+        let mut merged = definitions("groups:\n  - { id: pip, name: Pip }\n  - { id: npm, name: npm }\n");
+        merged.merge(definitions("groups:\n  - { id: pip, enabled: false }\n  - { id: gone, enabled: false }\n"));
+
+        let ids: Vec<_> = merged.groups.iter().map(|g| g.id.as_str()).collect();
+        assert_eq!(ids, ["npm"]);
+    }
+
+    #[test]
+    fn exclude_and_protected_lists_are_combined() {
+        let mut merged = definitions("exclude: ['*.lock']\nprotected: [/srv/a]\n");
+        merged.merge(definitions("exclude: [keep]\nprotected: [/srv/b]\n"));
+
+        let exclude: Vec<String> = merged.exclude.into_iter().map(String::from).collect();
+        assert_eq!(exclude, ["*.lock", "keep"]);
+        assert_eq!(merged.protected, ["/srv/a", "/srv/b"]);
+    }
+
+    #[test]
+    fn drop_ins_load_in_lexical_order() {
+        let dir = std::env::temp_dir().join(format!("tidytui-drop-ins-{}", std::process::id()));
+        fs::create_dir_all(dir.join("90-dir.yaml")).unwrap();
+        for name in ["50-b.yaml", "10-a.yaml", "99-z.yml", "README", "20-c.yaml"] {
+            fs::write(dir.join(name), "groups: []\n").unwrap();
+        }
+        let files = drop_in_files(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<_> = files.unwrap().iter().map(|f| f.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, ["10-a.yaml", "20-c.yaml", "50-b.yaml"]);
+    }
+
+    #[test]
+    fn age_round_trips_through_string() {
+        let age: Age = "3w".parse().unwrap();
+        assert_eq!(String::from(age).parse::<Age>(), Ok(age));
+    }
+}
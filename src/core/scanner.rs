@@ -1,49 +1,541 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::iter::Sum;
+use std::ops::Add;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use bytesize::ByteSize;
 use walkdir::WalkDir;
 use rayon::prelude::*;
-use crate::core::{CleanupItem, ItemStatus};
+use crate::core::{discovery, protected, trash, CleanupItem, ItemStatus};
+use crate::core::filter::FileFilter;
+use crate::core::registry::Target;
 
-pub fn scan_path(path: &Path) -> u64 {
+/// Space taken by the files of a tree, and when they last changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Sum of file lengths, like `du --apparent-size`.
+    pub apparent: u64,
+    /// Blocks actually allocated on disk, like `du`. Smaller than `apparent`
+    /// for sparse files, larger for many small files.
+    pub allocated: u64,
+    /// Most recent modification time of the files, including those too young
+    /// for an `older_than` filter.
+    pub modified: Option<SystemTime>,
+}
+
+impl Add for DiskUsage {
+    type Output = DiskUsage;
+
+    fn add(self, other: DiskUsage) -> DiskUsage {
+        DiskUsage {
+            apparent: self.apparent + other.apparent,
+            allocated: self.allocated + other.allocated,
+            modified: self.modified.max(other.modified),
+        }
+    }
+}
+
+impl Sum for DiskUsage {
+    fn sum<I: Iterator<Item = DiskUsage>>(iter: I) -> DiskUsage {
+        iter.fold(DiskUsage::default(), Add::add)
+    }
+}
+
+/// Files with several hardlinks already counted, by device and inode.
+type SeenInodes = Mutex<HashSet<(u64, u64)>>;
+
+/// Measures the regular files below `path` that pass `filter`. Files with several
+/// hardlinks inside the tree are counted once, as removing one link frees nothing.
+/// Symlinks, including `path` itself, are never followed, and other filesystems
+/// mounted below `path` are skipped unless `cross_mounts` is set.
+pub fn scan_path(path: &Path, filter: &FileFilter, cross_mounts: bool) -> DiskUsage {
+    scan_path_with(path, filter, cross_mounts, &SeenInodes::default())
+}
+
+/// Like [`scan_path`], but skips hardlinked files already in `seen`, so a file
+/// linked from several items is only counted for the first one scanned.
+fn scan_path_with(path: &Path, filter: &FileFilter, cross_mounts: bool, seen: &SeenInodes) -> DiskUsage {
+    let now = SystemTime::now();
     WalkDir::new(path)
+        .follow_root_links(false)
+        .same_file_system(!cross_mounts)
         .into_iter()
+        .filter_entry(|e| !filter.is_excluded(e.path(), path) && (cross_mounts || !is_nested_mount(e)))
         .filter_map(|e| e.ok())
         .filter_map(|e| e.metadata().ok())
         .filter(|m| m.is_file())
-        .map(|m| m.len())
+        .filter(|m| m.nlink() <= 1 || seen.lock().unwrap().insert((m.dev(), m.ino())))
+        .map(|m| {
+            let modified = m.modified().ok();
+            if filter.matches(&m, now) {
+                DiskUsage { apparent: m.len(), allocated: m.blocks() * 512, modified }
+            } else {
+                DiskUsage { modified, ..DiskUsage::default() }
+            }
+        })
         .sum()
 }
 
-pub fn scan_targets(targets: Vec<(String, String, String)>) -> Vec<CleanupItem> {
-    targets
+/// Whether `entry` is a directory below the walk root that something is mounted on.
+fn is_nested_mount(entry: &walkdir::DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_type().is_dir() && discovery::is_mount_point(entry.path())
+}
+
+/// Whether anything is mounted somewhere below `path`.
+pub fn contains_mount(path: &Path) -> bool {
+    WalkDir::new(path)
+        .follow_root_links(false)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .any(|e| is_nested_mount(&e))
+}
+
+/// Expands a leading `~` to the user's home directory, and environment variables
+/// written as `$VAR`, `${VAR}` or `${VAR:-default}`.
+pub fn expand_path(path_str: &str) -> PathBuf {
+    let expanded = expand_vars(path_str);
+    if let Some(without_tilde) = expanded.strip_prefix("~") {
+        if let Some(home) = dirs::home_dir() {
+            let without_slash = without_tilde.strip_prefix("/").unwrap_or(without_tilde);
+            return home.join(without_slash);
+        }
+    }
+    PathBuf::from(expanded)
+}
+
+/// Substitutes environment variables like a shell would. Variables that are unset
+/// (or empty) and have no default are left as written, so the path matches nothing
+/// rather than collapsing to e.g. `/cache`.
+fn expand_vars(s: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = s;
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+
+        if let Some(end) = after.strip_prefix('{').and_then(closing_brace) {
+            let inner = &after[1..end];
+            let (name, default) = match inner.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (inner, None),
+            };
+            match (lookup_var(name), default) {
+                (Some(value), _) => expanded.push_str(&value),
+                (None, Some(default)) => expanded.push_str(&expand_vars(default)),
+                (None, None) => expanded.push_str(&rest[dollar..dollar + 2 + end]),
+            }
+            rest = &after[end + 1..];
+            continue;
+        }
+
+        let len = after.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(after.len());
+        match lookup_var(&after[..len]) {
+            Some(value) if len > 0 => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[dollar..dollar + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Index of the `}` closing a `${`, relative to the `{`, allowing nested `${...}` defaults.
+fn closing_brace(braced: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (i, c) in braced.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+/// Looks up a variable for [`expand_vars`]. The XDG base directories fall back to
+/// their defaults (e.g. `~/.cache`) when unset, and `$HOME`, `$USER` and `$UID`
+/// work even when not exported.
+fn lookup_var(name: &str) -> Option<String> {
+    let path = match name {
+        "XDG_CACHE_HOME" => dirs::cache_dir(),
+        "XDG_CONFIG_HOME" => dirs::config_dir(),
+        "XDG_DATA_HOME" => dirs::data_dir(),
+        "XDG_STATE_HOME" => dirs::state_dir(),
+        "HOME" => dirs::home_dir(),
+        "UID" => return Some(trash::current_uid().to_string()),
+        "USER" => return env::var("USER").or_else(|_| env::var("LOGNAME")).ok().filter(|v| !v.is_empty()),
+        _ => return env::var(name).ok().filter(|v| !v.is_empty()),
+    };
+    path.map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Resolves a rule path to the existing paths it refers to, expanding glob patterns
+/// (`*`, `?`, `[...]` and `**`) when present.
+pub fn resolve_paths(path_str: &str) -> Vec<PathBuf> {
+    let path = expand_path(path_str);
+    let pattern = path.to_string_lossy();
+
+    if !pattern.contains(['*', '?', '[']) {
+        return if path.exists() { vec![path] } else { Vec::new() };
+    }
+
+    match glob::glob(&pattern) {
+        Ok(paths) => paths.filter_map(|p| p.ok()).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Progress reported by [`scan_targets_streaming`].
+#[derive(Debug)]
+pub enum ScanEvent {
+    Item(Box<CleanupItem>),
+    /// A target has been fully scanned; holds its rule path or command.
+    TargetDone(String),
+}
+
+pub fn scan_targets(targets: Vec<Target>) -> Vec<CleanupItem> {
+    let seen = SeenInodes::default();
+    targets.into_par_iter().flat_map(|target| scan_target(&target, &seen)).collect()
+}
+
+/// Scans `targets` like [`scan_targets`], but sends each item and finished target
+/// over `tx` as soon as it is ready. The channel closes once everything is scanned.
+pub fn scan_targets_streaming(targets: Vec<Target>, tx: Sender<ScanEvent>) {
+    let seen = SeenInodes::default();
+    targets.into_par_iter().for_each_with(tx, |tx, target| {
+        for item in scan_target(&target, &seen) {
+            let _ = tx.send(ScanEvent::Item(Box::new(item)));
+        }
+        let literal = target.literal_path.map(|p| p.display().to_string());
+        let label = target.command.or(target.path).or(literal).unwrap_or_default();
+        let _ = tx.send(ScanEvent::TargetDone(label));
+    });
+}
+
+/// Scans the items of `target`. Hardlinks are counted once across all targets
+/// scanned with the same `seen` set.
+fn scan_target(target: &Target, seen: &SeenInodes) -> Vec<CleanupItem> {
+    match &target.command {
+        Some(command) => scan_command_target(target, command, seen).into_iter().collect(),
+        None => scan_path_target(target, seen),
+    }
+}
+
+fn scan_path_target(target: &Target, seen: &SeenInodes) -> Vec<CleanupItem> {
+    let (mut paths, root) = match &target.literal_path {
+        Some(path) => (vec![path.clone()], path.clone()),
+        None => {
+            let path_str = target.path.as_deref().unwrap_or_default();
+            (resolve_paths(path_str), protected::declared_root(path_str))
+        }
+    };
+    // Checked before nested matches are folded into outer ones, so an outer match
+    // without its sibling does not hide the nested matches that have one.
+    if let Some(sibling) = &target.when_sibling {
+        paths.retain(|path| has_sibling(path, sibling));
+    }
+    // A pattern like `**/node_modules` also matches directories nested in other
+    // matches, which are cleaned along with them.
+    paths.sort();
+    paths.dedup_by(|nested, outer| nested.starts_with(outer));
+
+    paths
         .into_par_iter()
-        .filter_map(|(name, category, path_str)| {
-            // Expand ~ to user home if necessary
-            let path = if path_str.starts_with("~") {
-                if let Some(home) = dirs::home_dir() {
-                    let without_tilde = path_str.strip_prefix("~").unwrap_or(&path_str);
-                    let without_slash = without_tilde.strip_prefix("/").unwrap_or(without_tilde);
-                    home.join(without_slash)
-                } else {
-                    std::path::PathBuf::from(path_str)
-                }
-            } else {
-                std::path::PathBuf::from(path_str)
+        .filter(|path| !target.filter.is_excluded(path, path))
+        .map(|path| {
+            let symlink_target = fs::read_link(&path).ok().or_else(|| escaped_target(&path, &root));
+            let usage = match symlink_target {
+                Some(_) => DiskUsage::default(),
+                None => scan_path_with(&path, &target.filter, target.cross_mounts, seen),
             };
 
-            if path.exists() {
-                let size_bytes = scan_path(&path);
-                
-                Some(CleanupItem {
-                    name,
-                    category,
-                    path,
-                    size_bytes,
-                    selected: false,
-                    status: ItemStatus::Scanned,
-                })
-            } else {
-                None
+            CleanupItem {
+                group_id: target.group_id.clone(),
+                name: target.name.clone(),
+                category: target.category.clone(),
+                path,
+                command: None,
+                size_bytes: usage.apparent,
+                allocated_bytes: usage.allocated,
+                modified: usage.modified,
+                selected: false,
+                status: ItemStatus::Scanned,
+                mode: target.mode,
+                filter: target.filter.clone(),
+                cross_mounts: target.cross_mounts,
+                root: root.clone(),
+                symlink_target,
             }
         })
+        .filter(|item| !modified_within(item.modified, target.unmodified_for))
         .collect()
 }
+
+/// Where `path` really lies when a glob reached it through a symlinked directory
+/// below `root`, which would lead outside the rule. `None` if it stays inside.
+fn escaped_target(path: &Path, root: &Path) -> Option<PathBuf> {
+    let real = fs::canonicalize(path).ok()?;
+    let real_root = fs::canonicalize(root).ok()?;
+    (!real.starts_with(real_root)).then_some(real)
+}
+
+/// Whether a file matching `sibling`, a file name or glob pattern, lies next to `path`.
+fn has_sibling(path: &Path, sibling: &str) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    if !sibling.contains(['*', '?', '[']) {
+        return parent.join(sibling).exists();
+    }
+    let pattern = Path::new(&glob::Pattern::escape(&parent.to_string_lossy())).join(sibling);
+    glob::glob(&pattern.to_string_lossy()).is_ok_and(|mut matches| matches.any(|m| m.is_ok()))
+}
+
+/// Whether `modified` lies less than `period` ago. Never true without a period,
+/// or for paths without files.
+fn modified_within(modified: Option<SystemTime>, period: Option<Duration>) -> bool {
+    match (modified, period) {
+        (Some(modified), Some(period)) => modified.elapsed().map_or(true, |elapsed| elapsed < period),
+        _ => false,
+    }
+}
+
+/// Builds the single item for a command rule, or nothing if the command's program
+/// is not installed. The size comes from `size_command`, else from the rule path.
+fn scan_command_target(target: &Target, command: &str, seen: &SeenInodes) -> Option<CleanupItem> {
+    let program = command.split_whitespace().next()?;
+    discovery::find_in_path(program)?;
+
+    let paths = target.path.as_deref().map(resolve_paths).unwrap_or_default();
+    let usage = match &target.size_command {
+        // A size command reports a single figure, taken as both sizes.
+        Some(size_command) => {
+            let size = run_size_command(size_command).unwrap_or(0);
+            DiskUsage { apparent: size, allocated: size, modified: None }
+        }
+        None => paths.iter().map(|p| scan_path_with(p, &target.filter, target.cross_mounts, seen)).sum(),
+    };
+
+    Some(CleanupItem {
+        group_id: target.group_id.clone(),
+        name: target.name.clone(),
+        category: target.category.clone(),
+        path: paths.into_iter().next().unwrap_or_default(),
+        command: Some(command.to_string()),
+        size_bytes: usage.apparent,
+        allocated_bytes: usage.allocated,
+        modified: None,
+        selected: false,
+        status: ItemStatus::Scanned,
+        mode: target.mode,
+        filter: target.filter.clone(),
+        cross_mounts: target.cross_mounts,
+        root: protected::declared_root(target.path.as_deref().unwrap_or_default()),
+        symlink_target: None,
+    })
+}
+
+/// Runs a `size_command` and adds up the first size found on each output line,
+/// so both `du -sb` and `journalctl --disk-usage` style output work.
+fn run_size_command(command: &str) -> Option<u64> {
+    let output = Command::new("sh").arg("-c").arg(command).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let total = stdout
+        .lines()
+        .filter_map(|line| {
+            line.split_whitespace()
+                .map(|token| token.trim_matches(|c: char| !c.is_ascii_alphanumeric()))
+                .find_map(|token| token.parse::<ByteSize>().ok())
+        })
+        .map(|size| size.0)
+        .sum();
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each test uses its own variables, as tests run in parallel and share the environment.
+
+    #[test]
+    fn expands_plain_and_braced_variables() {
+        env::set_var("TIDYTUI_TEST_PLAIN", "/opt/x");
+        assert_eq!(expand_vars("$TIDYTUI_TEST_PLAIN/cache"), "/opt/x/cache");
+        assert_eq!(expand_vars("${TIDYTUI_TEST_PLAIN}cache"), "/opt/xcache");
+    }
+
+    #[test]
+    fn nested_default_is_expanded() {
+        env::remove_var("TIDYTUI_TEST_UNSET_A");
+        env::set_var("TIDYTUI_TEST_NESTED_B", "/b");
+        assert_eq!(expand_vars("${TIDYTUI_TEST_UNSET_A:-${TIDYTUI_TEST_NESTED_B}}/c"), "/b/c");
+        assert_eq!(expand_vars("${TIDYTUI_TEST_UNSET_A:-/d}/c"), "/d/c");
+    }
+
+    #[test]
+    fn set_variable_wins_over_default() {
+        env::set_var("TIDYTUI_TEST_SET", "/set");
+        assert_eq!(expand_vars("${TIDYTUI_TEST_SET:-/default}"), "/set");
+    }
+
+    #[test]
+    fn unset_variable_is_left_as_written() {
+        env::remove_var("TIDYTUI_TEST_FOO");
+        assert_eq!(expand_vars("$TIDYTUI_TEST_FOO/cache"), "$TIDYTUI_TEST_FOO/cache");
+        assert_eq!(expand_vars("${TIDYTUI_TEST_FOO}/cache"), "${TIDYTUI_TEST_FOO}/cache");
+    }
+
+    #[test]
+    fn empty_variable_counts_as_unset() {
+        env::set_var("TIDYTUI_TEST_EMPTY", "");
+        assert_eq!(expand_vars("$TIDYTUI_TEST_EMPTY/cache"), "$TIDYTUI_TEST_EMPTY/cache");
+        assert_eq!(expand_vars("${TIDYTUI_TEST_EMPTY:-/d}"), "/d");
+    }
+
+    #[test]
+    fn trailing_and_lone_dollar_are_kept() {
+        assert_eq!(expand_vars("/tmp/cost$"), "/tmp/cost$");
+        assert_eq!(expand_vars("/tmp/$/x"), "/tmp/$/x");
+    }
+
+    #[test]
+    fn unclosed_brace_is_kept() {
+        assert_eq!(expand_vars("/tmp/${UNCLOSED"), "/tmp/${UNCLOSED");
+        assert_eq!(expand_vars("/tmp/${A:-${B}"), "/tmp/${A:-${B}");
+    }
+
+    #[test]
+    fn closing_brace_skips_nested_braces() {
+        assert_eq!(closing_brace("A}rest"), Some(2));
+        assert_eq!(closing_brace("A:-${B}}rest"), Some(8));
+        assert_eq!(closing_brace("A:-${B}"), None);
+        assert_eq!(closing_brace(""), None);
+    }
+
+    #[test]
+    fn xdg_variables_fall_back_to_defaults() {
+        let expected = dirs::cache_dir().unwrap().join("pip");
+        assert_eq!(expand_path("$XDG_CACHE_HOME/pip"), expected);
+    }
+
+    #[test]
+    fn scan_path_counts_only_stale_files() {
+        let dir = env::temp_dir().join(format!("tidytui-scanner-age-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let old = SystemTime::now() - Duration::from_secs(60 * 24 * 60 * 60);
+        fs::write(dir.join("old"), [0; 100]).unwrap();
+        let file = fs::File::options().write(true).open(dir.join("old")).unwrap();
+        file.set_times(fs::FileTimes::new().set_accessed(old).set_modified(old)).unwrap();
+        fs::write(dir.join("new"), [0; 50]).unwrap();
+        let new_modified = fs::metadata(dir.join("new")).unwrap().modified().ok();
+
+        let filter = FileFilter { older_than: Some(Duration::from_secs(30 * 24 * 60 * 60)), exclude: Vec::new() };
+        let usage = scan_path(&dir, &filter, false);
+        let unfiltered = scan_path(&dir, &FileFilter::default(), false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(usage.apparent, 100);
+        assert_eq!(usage.modified, new_modified);
+        assert_eq!(unfiltered.apparent, 150);
+    }
+
+    fn target(path: &str) -> Target {
+        Target {
+            group_id: "test".to_string(),
+            name: "Test".to_string(),
+            category: "Test".to_string(),
+            path: Some(path.to_string()),
+            literal_path: None,
+            command: None,
+            size_command: None,
+            mode: crate::core::CleanMode::Whole,
+            filter: FileFilter::default(),
+            cross_mounts: false,
+            when_sibling: None,
+            unmodified_for: None,
+        }
+    }
+
+    #[test]
+    fn hardlinks_shared_between_items_are_counted_once() {
+        let dir = env::temp_dir().join(format!("tidytui-scanner-hardlinks-{}", std::process::id()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::create_dir_all(dir.join("b")).unwrap();
+        fs::write(dir.join("a/shared"), [0; 100]).unwrap();
+        fs::hard_link(dir.join("a/shared"), dir.join("b/shared")).unwrap();
+        fs::write(dir.join("b/own"), [0; 10]).unwrap();
+
+        let targets = vec![target(&format!("{}/a", dir.display())), target(&format!("{}/b", dir.display()))];
+        let total: u64 = scan_targets(targets).iter().map(|item| item.size_bytes).sum();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(total, 110);
+    }
+
+    #[test]
+    fn has_sibling_matches_names_and_globs() {
+        let dir = env::temp_dir().join(format!("tidytui-scanner-sibling-{}", std::process::id()));
+        fs::create_dir_all(dir.join("project/target")).unwrap();
+        fs::write(dir.join("project/Cargo.toml"), b"").unwrap();
+        fs::write(dir.join("project/app.csproj"), b"").unwrap();
+        let target = dir.join("project/target");
+
+        let found = [
+            has_sibling(&target, "Cargo.toml"),
+            has_sibling(&target, "*.csproj"),
+            has_sibling(&target, "package.json"),
+            has_sibling(&target, "*.sln"),
+        ];
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found, [true, true, false, false]);
+    }
+
+    #[test]
+    fn when_sibling_is_checked_before_nested_matches_are_folded() {
+        let dir = env::temp_dir().join(format!("tidytui-scanner-nested-{}", std::process::id()));
+        fs::create_dir_all(dir.join("build/app/build")).unwrap();
+        fs::write(dir.join("build/app/build.gradle"), b"").unwrap();
+        let target = Target {
+            when_sibling: Some("build.gradle".to_string()),
+            ..target(&format!("{}/**/build", dir.display()))
+        };
+        let items = scan_path_target(&target, &SeenInodes::default());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let paths: Vec<_> = items.into_iter().map(|item| item.path).collect();
+        assert_eq!(paths, [dir.join("build/app/build")]);
+    }
+
+    #[test]
+    fn modified_within_needs_a_period_and_a_time() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let yesterday = SystemTime::now() - day;
+        assert!(modified_within(Some(yesterday), Some(7 * day)));
+        assert!(!modified_within(Some(yesterday - 30 * day), Some(7 * day)));
+        assert!(!modified_within(Some(yesterday), None));
+        assert!(!modified_within(None, Some(7 * day)));
+        // A clock set back leaves files in the future, which count as fresh.
+        assert!(modified_within(Some(SystemTime::now() + day), Some(7 * day)));
+    }
+
+    #[test]
+    fn tilde_expands_to_home() {
+        assert_eq!(expand_path("~/.npm"), dirs::home_dir().unwrap().join(".npm"));
+        assert_eq!(expand_path("~"), dirs::home_dir().unwrap());
+    }
+}
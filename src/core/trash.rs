@@ -0,0 +1,188 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+
+/// A trash directory as described by the freedesktop.org Trash specification,
+/// holding `files/` and `info/` subdirectories.
+struct TrashDir {
+    root: PathBuf,
+    /// For per-mount trash directories, the mount point that `Path=` entries are
+    /// written relative to. `None` for the home trash, which uses absolute paths.
+    topdir: Option<PathBuf>,
+}
+
+/// The home trash, `$XDG_DATA_HOME/Trash`.
+pub fn home_trash() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("Trash"))
+}
+
+/// Whether `path` lives inside one of the user's trash directories, where trashing
+/// it again would make no sense.
+pub fn is_in_trash(path: &Path) -> bool {
+    if home_trash().is_some_and(|trash| path.starts_with(trash)) {
+        return true;
+    }
+    let uid = current_uid().to_string();
+    let per_mount = format!(".Trash-{}", uid);
+    path.ancestors().any(|p| {
+        let name = p.file_name().map(|n| n.to_string_lossy());
+        let parent_name = p.parent().and_then(|p| p.file_name()).map(|n| n.to_string_lossy());
+        name.as_deref() == Some(per_mount.as_str())
+            || (name.as_deref() == Some(uid.as_str()) && parent_name.as_deref() == Some(".Trash"))
+    })
+}
+
+/// Moves `path` into the trash directory on the same filesystem and records a
+/// `.trashinfo` file so it can be restored by any spec-compliant file manager.
+pub fn move_to_trash(path: &Path) -> Result<()> {
+    let path = std::path::absolute(path).context("Failed to resolve path")?;
+    let trash = trash_dir_for(&path)?;
+
+    let files_dir = trash.root.join("files");
+    let info_dir = trash.root.join("info");
+    create_private_dir(&files_dir)?;
+    create_private_dir(&info_dir)?;
+
+    let recorded_path = match &trash.topdir {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path).to_path_buf(),
+        None => path.clone(),
+    };
+
+    let file_name = path
+        .file_name()
+        .context("Cannot trash a path without a file name")?
+        .to_string_lossy()
+        .into_owned();
+
+    // Reserve a unique name by creating the info file exclusively, as the spec requires.
+    let mut counter = 1;
+    let (trash_name, info_path, mut info_file) = loop {
+        let candidate = match counter {
+            1 => file_name.clone(),
+            n => format!("{}.{}", file_name, n),
+        };
+        let info_path = info_dir.join(format!("{}.trashinfo", candidate));
+        match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(file) => break (candidate, info_path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => return Err(e).context("Failed to create trash info file"),
+        }
+    };
+
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        url_encode(&recorded_path),
+        local_timestamp()
+    );
+    if let Err(e) = info_file.write_all(info.as_bytes()) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e).context("Failed to write trash info file");
+    }
+
+    if let Err(e) = fs::rename(&path, files_dir.join(&trash_name)) {
+        let _ = fs::remove_file(&info_path);
+        return Err(e).context("Failed to move item to trash");
+    }
+
+    Ok(())
+}
+
+/// Picks the trash directory for `path`: the home trash when it is on the same
+/// filesystem, otherwise `$topdir/.Trash/$uid` or `$topdir/.Trash-$uid` on the
+/// mount that contains it.
+fn trash_dir_for(path: &Path) -> Result<TrashDir> {
+    let device = fs::symlink_metadata(path).context("Failed to read item metadata")?.dev();
+
+    if let Some(home_trash) = home_trash() {
+        // The trash may not exist yet, so compare against its closest existing ancestor.
+        let home_device = home_trash
+            .ancestors()
+            .find_map(|p| fs::metadata(p).ok())
+            .map(|m| m.dev());
+        if home_device == Some(device) {
+            return Ok(TrashDir { root: home_trash, topdir: None });
+        }
+    }
+
+    let topdir = mount_point(path, device);
+    let uid = current_uid();
+
+    // $topdir/.Trash must be a real directory with the sticky bit set to be used.
+    let admin_trash = topdir.join(".Trash");
+    if let Ok(meta) = fs::symlink_metadata(&admin_trash) {
+        if meta.is_dir() && meta.permissions().mode() & 0o1000 != 0 {
+            let root = admin_trash.join(uid.to_string());
+            if create_private_dir(&root).is_ok() {
+                return Ok(TrashDir { root, topdir: Some(topdir) });
+            }
+        }
+    }
+
+    let root = topdir.join(format!(".Trash-{}", uid));
+    create_private_dir(&root)
+        .with_context(|| format!("No usable trash directory on the filesystem of {}", path.display()))?;
+    Ok(TrashDir { root, topdir: Some(topdir) })
+}
+
+/// Walks up from `path` to the topmost ancestor that is still on `device`.
+fn mount_point(path: &Path, device: u64) -> PathBuf {
+    let mut topdir = path.to_path_buf();
+    for ancestor in path.ancestors().skip(1) {
+        match fs::metadata(ancestor) {
+            Ok(meta) if meta.dev() == device => topdir = ancestor.to_path_buf(),
+            _ => break,
+        }
+    }
+    topdir
+}
+
+fn create_private_dir(path: &Path) -> Result<()> {
+    if fs::symlink_metadata(path).is_ok_and(|m| m.is_dir()) {
+        return Ok(());
+    }
+    fs::create_dir_all(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    unsafe { libc::getuid() }
+}
+
+/// Percent-encodes a path for the `Path=` key, keeping `/` and unreserved characters.
+fn url_encode(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// The current local time as `YYYY-MM-DDThh:mm:ss`, the format used by `DeletionDate=`.
+pub fn local_timestamp() -> String {
+    // SAFETY: time and localtime_r only write into the locals passed to them.
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            tm.tm_year + 1900,
+            tm.tm_mon + 1,
+            tm.tm_mday,
+            tm.tm_hour,
+            tm.tm_min,
+            tm.tm_sec
+        )
+    }
+}
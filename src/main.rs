@@ -1,4 +1,7 @@
-use std::{error::Error, io, time::Duration};
+use std::{error::Error, io, path::PathBuf, thread, time::Duration};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -8,14 +11,119 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
+use clap::{Parser, Subcommand};
 
+mod cli;
 mod core;
 mod tui;
 
-use crate::core::{discovery, registry, scanner};
+use crate::cli::OutputFormat;
+use crate::core::{cleaner, discovery, quarantine, scanner, CleanupItem, RunMode};
+use crate::core::cleaner::CleanEvent;
+use crate::core::protected::ProtectedPaths;
+use crate::core::quarantine::QuarantineRun;
+use crate::core::registry::Target;
+use crate::core::scanner::ScanEvent;
 use crate::tui::{app::{App, AppState}, ui};
 
+#[derive(Parser)]
+#[command(name = "tidytui", version, about)]
+struct Cli {
+    /// Extra definitions file layered on top of the system, user and current directory ones, may be repeated
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Vec<PathBuf>,
+    /// Look for build artifacts (target/, node_modules/, ...) of the projects below DIR instead of using the definitions, may be repeated
+    #[arg(long, value_name = "DIR", global = true)]
+    projects: Vec<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scan for cleanable items and print them without starting the TUI
+    Scan {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+    /// Clean the given groups without starting the TUI (dry-run unless --trash, --quarantine or --yes is passed)
+    Clean {
+        /// Group id to clean (see definitions.yaml), may be repeated
+        #[arg(long = "group", value_name = "ID", required = true)]
+        groups: Vec<String>,
+        /// Only report what would be deleted (default)
+        #[arg(long, group = "mode")]
+        dry_run: bool,
+        /// Move items to the trash instead of deleting them
+        #[arg(long, group = "mode")]
+        trash: bool,
+        /// Move items to the quarantine area so the run can be undone
+        #[arg(long, group = "mode")]
+        quarantine: bool,
+        /// Permanently delete files
+        #[arg(long, group = "mode")]
+        yes: bool,
+    },
+    /// Restore the items of a quarantined run (the latest one by default)
+    Undo {
+        /// Run id as printed by `clean --quarantine` or `undo --list`
+        run_id: Option<String>,
+        /// List quarantined runs instead of restoring one
+        #[arg(long, conflicts_with = "run_id")]
+        list: bool,
+    },
+    /// Check the definitions files for errors
+    CheckConfig,
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Scan { format }) => {
+            cli::run_scan(&cli.config, &cli.projects, format)?;
+            Ok(())
+        }
+        Some(Command::Clean { groups, dry_run: _, trash, quarantine, yes }) => {
+            let mode = if trash {
+                RunMode::Trash
+            } else if quarantine {
+                RunMode::Quarantine
+            } else if yes {
+                RunMode::Delete
+            } else {
+                RunMode::DryRun
+            };
+            if !cli::run_clean(&cli.config, &cli.projects, &groups, mode)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(Command::Undo { run_id, list }) => {
+            if !cli::run_undo(&cli.config, run_id.as_deref(), list)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Some(Command::CheckConfig) => {
+            if !cli::run_check_config(&cli.config)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        None => run_tui(&cli.config, &cli.projects),
+    }
+}
+
+fn run_tui(config: &[PathBuf], projects: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    // Load definitions first so config errors are printed on a normal terminal
+    let system = discovery::detect_system();
+    let definitions = cli::load_definitions(config, projects)?;
+    let targets = cli::find_targets(&definitions, &system, projects);
+    // Expired runs can no longer be undone, so failing to purge them is not fatal.
+    let _ = quarantine::purge_expired(definitions.quarantine_retention());
+
     // 1. Setup Terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -24,21 +132,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // 2. Initialize App State
-    let mut app = App::new();
-
-    // 3. Scan Phase (Synchronous for MVP)
-    // In a real app, this would be async or thread-pooled with UI updates
-    let os_type = discovery::detect_os();
-    let definitions = registry::load_definitions()?;
-    let targets = registry::filter_rules(&definitions, &os_type);
-    
-    // Simple "Loading" indication could go here if we had a render loop running, 
-    // but for MVP we just block on scan.
-    let items = scanner::scan_targets(targets);
-    app.set_items(items);
-
-    // 4. Run App Loop
-    let res = run_app(&mut terminal, app);
+    let app = App::new(system);
+
+    // 3. Run App Loop, scanning in the background
+    let res = run_app(&mut terminal, app, &targets, &definitions.protected_paths());
 
     // 5. Cleanup Terminal
     disable_raw_mode()?;
@@ -56,52 +153,103 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+/// Scans `targets` on a worker thread, returning the channel its results arrive on.
+fn start_scan(app: &mut App, targets: &[Target]) -> Receiver<ScanEvent> {
+    let (tx, rx) = mpsc::channel();
+    let targets = targets.to_vec();
+    app.start_scanning(targets.len());
+    thread::spawn(move || scanner::scan_targets_streaming(targets, tx));
+    rx
+}
+
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut app: App,
+    targets: &[Target],
+    protected: &ProtectedPaths,
+) -> io::Result<()> {
+    let mut scan_rx = Some(start_scan(&mut app, targets));
+    let mut clean_rx: Option<Receiver<CleanEvent>> = None;
+    let cancel = Arc::new(AtomicBool::new(false));
+    let mut quarantine_id = None;
+
     loop {
-        terminal.draw(|f| ui::ui(f, &mut app))?;
+        if let Some(rx) = &scan_rx {
+            let mut items = Vec::new();
+            let mut finished = false;
+            loop {
+                match rx.try_recv() {
+                    Ok(ScanEvent::Item(item)) => items.push(*item),
+                    Ok(ScanEvent::TargetDone(target)) => app.target_scanned(target),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            app.add_items(items);
+            if finished {
+                app.scan_finished();
+                scan_rx = None;
+            }
+        }
 
-        if let AppState::Cleaning { .. } = app.app_state {
-            let selected_indices: Vec<usize> = app.items.iter().enumerate()
-                .filter(|(_, i)| i.selected && !matches!(i.status, crate::core::ItemStatus::Deleted))
-                .map(|(idx, _)| idx)
-                .collect();
-            
-            let total = selected_indices.len();
-            let dry_run = app.dry_run;
-
-            for (i, &idx) in selected_indices.iter().enumerate() {
-                let item_name = app.items[idx].name.clone();
-                app.app_state = AppState::Cleaning { 
-                    current: i + 1, 
-                    total, 
-                    item_name: item_name.clone() 
-                };
-                
-                terminal.draw(|f| ui::ui(f, &mut app))?;
+        terminal.draw(|f| ui::ui(f, &mut app))?;
 
-                match crate::core::cleaner::clean_item(&mut app.items[idx], dry_run) {
-                    Ok(_) => {},
-                    Err(e) => {
-                        app.items[idx].status = crate::core::ItemStatus::Failed(e.to_string());
+        if let Some(rx) = &clean_rx {
+            let mut finished = false;
+            loop {
+                match rx.try_recv() {
+                    Ok(CleanEvent::Started(idx)) => app.item_clean_started(idx),
+                    Ok(CleanEvent::File(path, bytes)) => app.file_cleaned(&path, bytes),
+                    Ok(CleanEvent::Finished(idx, status)) => app.item_clean_finished(idx, status),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
                     }
                 }
-                
-                std::thread::sleep(Duration::from_millis(200));
             }
-            
-            app.cleanup_finished();
-            app.app_state = AppState::Viewing;
-            app.active_tab = crate::tui::app::Tab::Dashboard; // Go to dashboard to see updated stats
-            continue;
+            if finished {
+                app.cleanup_finished();
+                app.notice = if cancel.load(Ordering::Relaxed) {
+                    Some("Cleaning cancelled".to_string())
+                } else {
+                    quarantine_id.take().map(|id| format!("Quarantined as run {}, press <u> to undo", id))
+                };
+                app.app_state = AppState::Viewing;
+                app.active_tab = crate::tui::app::Tab::Dashboard; // Go to dashboard to see updated stats
+                clean_rx = None;
+            }
         }
 
-        if event::poll(Duration::from_millis(250))? {
+        // Poll faster while working in the background so progress keeps moving
+        let timeout = if scan_rx.is_some() || clean_rx.is_some() { 50 } else { 250 };
+        if event::poll(Duration::from_millis(timeout))? {
             if let Event::Key(key) = event::read()? {
+                let scanning = scan_rx.is_some();
                 match app.app_state {
-                    AppState::Viewing => {
+                    AppState::Viewing | AppState::Scanning { .. } => {
+                        app.notice = None;
                         match key.code {
                             KeyCode::Char('q') => return Ok(()),
-                            KeyCode::Char('d') => app.toggle_dry_run(),
+                            KeyCode::Char('d') => app.cycle_mode(),
+                            KeyCode::Char('u') if !scanning => {
+                                app.notice = Some(match quarantine::undo(None) {
+                                    Ok(report) if report.conflicts.is_empty() => {
+                                        format!("Restored {} items from run {}", report.restored, report.run_id)
+                                    }
+                                    Ok(report) => format!(
+                                        "Restored {} items from run {}, {} kept in quarantine",
+                                        report.restored,
+                                        report.run_id,
+                                        report.conflicts.len()
+                                    ),
+                                    Err(e) => format!("Undo failed: {:#}", e),
+                                });
+                                scan_rx = Some(start_scan(&mut app, targets));
+                            }
                             KeyCode::Char('1') => app.active_tab = crate::tui::app::Tab::Dashboard,
                             KeyCode::Char('2') => app.active_tab = crate::tui::app::Tab::Results,
                             KeyCode::Char('3') => app.active_tab = crate::tui::app::Tab::Help,
@@ -110,10 +258,8 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                             KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab => app.next_tab(),
                             KeyCode::Char('h') | KeyCode::Left | KeyCode::BackTab => app.previous_tab(),
                             KeyCode::Char(' ') => app.toggle_selection(),
-                            KeyCode::Enter => {
-                                if app.items.iter().any(|i| i.selected) {
-                                    app.app_state = AppState::Confirming;
-                                }
+                            KeyCode::Enter if !scanning && app.items.iter().any(|i| i.selected) => {
+                                app.app_state = AppState::Confirming;
                             }
                             _ => {}
                         }
@@ -121,8 +267,34 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                     AppState::Confirming => {
                         match key.code {
                             KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
-                                let total = app.items.iter().filter(|i| i.selected).count();
-                                app.app_state = AppState::Cleaning { current: 0, total, item_name: String::new() };
+                                let items: Vec<(usize, CleanupItem)> = app.items.iter().enumerate()
+                                    .filter(|(_, i)| i.selected && !i.status.is_cleaned())
+                                    .map(|(idx, i)| (idx, i.clone()))
+                                    .collect();
+                                let total_bytes = items.iter().map(|(_, i)| i.size_bytes).sum();
+                                let mode = app.mode;
+                                let run = match mode {
+                                    RunMode::Quarantine => match QuarantineRun::new() {
+                                        Ok(run) => Some(run),
+                                        Err(e) => {
+                                            app.notice = Some(format!("Cannot start quarantine run: {:#}", e));
+                                            app.app_state = AppState::Viewing;
+                                            continue;
+                                        }
+                                    },
+                                    _ => None,
+                                };
+                                quarantine_id = run.as_ref().map(|r| r.id.clone());
+                                cancel.store(false, Ordering::Relaxed);
+
+                                let (tx, rx) = mpsc::channel();
+                                let worker_cancel = Arc::clone(&cancel);
+                                let protected = protected.clone();
+                                app.start_cleaning(items.len(), total_bytes);
+                                thread::spawn(move || {
+                                    cleaner::clean_items_streaming(items, mode, run, protected, worker_cancel, tx)
+                                });
+                                clean_rx = Some(rx);
                             }
                             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                                 app.app_state = AppState::Viewing;
@@ -130,7 +302,12 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
                             _ => {}
                         }
                     }
-                    _ => {}
+                    AppState::Cleaning(_) => {
+                        if key.code == KeyCode::Esc {
+                            cancel.store(true, Ordering::Relaxed);
+                            app.cancel_cleaning();
+                        }
+                    }
                 }
             }
         }
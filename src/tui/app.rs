@@ -1,5 +1,8 @@
-use crate::core::CleanupItem;
+use crate::core::{CleanupItem, ItemStatus, RunMode};
+use crate::core::discovery::System;
 use ratatui::widgets::ListState;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Tab {
@@ -17,9 +20,60 @@ pub enum ResultRow {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AppState {
+    /// Results are still streaming in from the background scan.
+    Scanning { done: usize, total: usize, current: String },
     Viewing,
     Confirming,
-    Cleaning { current: usize, total: usize, item_name: String },
+    Cleaning(CleanProgress),
+}
+
+/// Progress of a running clean, weighted by the scanned size of each item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanProgress {
+    pub current: usize,
+    pub total: usize,
+    pub item_name: String,
+    pub file: String,
+    pub cancelling: bool,
+    pub files_removed: u64,
+    pub total_bytes: u64,
+    started: Instant,
+    /// Scanned size of the items already finished.
+    finished_bytes: u64,
+    /// Scanned size of the item being cleaned, and the bytes freed from it so far.
+    item_bytes: u64,
+    item_freed: u64,
+}
+
+impl CleanProgress {
+    /// Bytes processed so far. The current item never counts for more than its
+    /// scanned size, so files written since the scan cannot push the gauge past 100%.
+    pub fn bytes_done(&self) -> u64 {
+        self.finished_bytes + self.item_freed.min(self.item_bytes)
+    }
+
+    pub fn ratio(&self) -> f64 {
+        match self.total_bytes {
+            0 => self.current as f64 / self.total.max(1) as f64,
+            total => (self.bytes_done() as f64 / total as f64).min(1.0),
+        }
+    }
+
+    /// Average bytes per second since cleaning started.
+    pub fn throughput(&self) -> f64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 { self.bytes_done() as f64 / elapsed } else { 0.0 }
+    }
+
+    /// Estimated time until all bytes are processed, once there is a rate to go by.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.throughput();
+        if rate <= 0.0 {
+            return None;
+        }
+        let remaining = self.total_bytes.saturating_sub(self.bytes_done());
+        Some(Duration::from_secs_f64(remaining as f64 / rate))
+    }
 }
 
 pub struct App {
@@ -27,21 +81,27 @@ pub struct App {
     pub rendered_rows: Vec<ResultRow>,
     pub state: ListState,
     pub total_size: u64,
-    pub dry_run: bool,
+    pub mode: RunMode,
     pub active_tab: Tab,
     pub app_state: AppState,
+    /// One-off message shown in the footer until the next key press.
+    pub notice: Option<String>,
+    /// The system the rules were matched against, shown on the dashboard.
+    pub system: System,
 }
 
 impl App {
-    pub fn new() -> App {
+    pub fn new(system: System) -> App {
         App {
             items: Vec::new(),
             rendered_rows: Vec::new(),
             state: ListState::default(),
             total_size: 0,
-            dry_run: true, // Safety default
+            mode: RunMode::DryRun, // Safety default
             active_tab: Tab::Dashboard,
             app_state: AppState::Viewing,
+            notice: None,
+            system,
         }
     }
 
@@ -56,6 +116,51 @@ impl App {
         }
     }
 
+    pub fn start_scanning(&mut self, total: usize) {
+        self.set_items(Vec::new());
+        self.app_state = AppState::Scanning { done: 0, total, current: String::new() };
+    }
+
+    /// Adds freshly scanned items while keeping the cursor on the same item.
+    pub fn add_items(&mut self, items: Vec<CleanupItem>) {
+        if items.is_empty() {
+            return;
+        }
+
+        let selected_item = self.state.selected().and_then(|i| match self.rendered_rows.get(i) {
+            Some(ResultRow::Item(idx)) => Some(*idx),
+            _ => None,
+        });
+
+        self.items.extend(items);
+        self.total_size = self.items.iter().map(|i| i.size_bytes).sum();
+        self.calculate_rendered_rows();
+
+        match selected_item {
+            Some(idx) => {
+                let row = self.rendered_rows.iter().position(|r| *r == ResultRow::Item(idx));
+                self.state.select(row);
+            }
+            None => {
+                self.state.select(Some(0));
+                self.next();
+            }
+        }
+    }
+
+    pub fn target_scanned(&mut self, target: String) {
+        if let AppState::Scanning { done, current, .. } = &mut self.app_state {
+            *done += 1;
+            *current = target;
+        }
+    }
+
+    pub fn scan_finished(&mut self) {
+        if matches!(self.app_state, AppState::Scanning { .. }) {
+            self.app_state = AppState::Viewing;
+        }
+    }
+
     fn calculate_rendered_rows(&mut self) {
         let mut rows = Vec::new();
         let mut categories: Vec<String> = self.items.iter().map(|i| i.category.clone()).collect();
@@ -122,8 +227,13 @@ impl App {
         }
     }
     
-    pub fn toggle_dry_run(&mut self) {
-        self.dry_run = !self.dry_run;
+    pub fn cycle_mode(&mut self) {
+        self.mode = match self.mode {
+            RunMode::DryRun => RunMode::Trash,
+            RunMode::Trash => RunMode::Quarantine,
+            RunMode::Quarantine => RunMode::Delete,
+            RunMode::Delete => RunMode::DryRun,
+        };
     }
 
     pub fn next_tab(&mut self) {
@@ -142,10 +252,59 @@ impl App {
         };
     }
 
+    pub fn start_cleaning(&mut self, total: usize, total_bytes: u64) {
+        self.app_state = AppState::Cleaning(CleanProgress {
+            current: 0,
+            total,
+            item_name: String::new(),
+            file: String::new(),
+            cancelling: false,
+            files_removed: 0,
+            total_bytes,
+            started: Instant::now(),
+            finished_bytes: 0,
+            item_bytes: 0,
+            item_freed: 0,
+        });
+    }
+
+    pub fn item_clean_started(&mut self, idx: usize) {
+        let item = &self.items[idx];
+        if let AppState::Cleaning(progress) = &mut self.app_state {
+            progress.current += 1;
+            progress.item_name = item.name.clone();
+            progress.file.clear();
+            progress.item_bytes = item.size_bytes;
+            progress.item_freed = 0;
+        }
+    }
+
+    pub fn file_cleaned(&mut self, path: &Path, bytes: u64) {
+        if let AppState::Cleaning(progress) = &mut self.app_state {
+            progress.file = path.display().to_string();
+            progress.files_removed += 1;
+            progress.item_freed += bytes;
+        }
+    }
+
+    pub fn item_clean_finished(&mut self, idx: usize, status: ItemStatus) {
+        self.items[idx].status = status;
+        if let AppState::Cleaning(progress) = &mut self.app_state {
+            progress.finished_bytes += progress.item_bytes;
+            progress.item_bytes = 0;
+            progress.item_freed = 0;
+        }
+    }
+
+    pub fn cancel_cleaning(&mut self) {
+        if let AppState::Cleaning(progress) = &mut self.app_state {
+            progress.cancelling = true;
+        }
+    }
+
     pub fn cleanup_finished(&mut self) {
-        use crate::core::ItemStatus;
-        // Keep only items that were not successfully deleted
-        self.items.retain(|i| !matches!(i.status, ItemStatus::Deleted));
+        // Keep only items that were not successfully cleaned
+        self.items.retain(|i| !i.status.is_cleaned());
         self.total_size = self.items.iter().map(|i| i.size_bytes).sum();
         self.calculate_rendered_rows();
         self.state.select(Some(0));
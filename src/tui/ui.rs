@@ -6,8 +6,9 @@ use ratatui::{
     Frame,
 };
 use bytesize::ByteSize;
-use crate::tui::app::{App, Tab, ResultRow, AppState};
-use crate::core::ItemStatus;
+use std::time::Duration;
+use crate::tui::app::{App, Tab, ResultRow, AppState, CleanProgress};
+use crate::core::{ItemStatus, RunMode};
 
 pub fn ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
@@ -35,6 +36,19 @@ pub fn ui(f: &mut Frame, app: &mut App) {
 
     // --- Content ---
     match app.app_state {
+        AppState::Scanning { done, total, ref current } => {
+            let current = current.clone();
+            let scan_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(chunks[1]);
+            render_scan_progress(f, done, total, &current, scan_chunks[0]);
+            match app.active_tab {
+                Tab::Dashboard => render_dashboard(f, app, scan_chunks[1]),
+                Tab::Results => render_results(f, app, scan_chunks[1]),
+                Tab::Help => render_help(f, app, scan_chunks[1]),
+            }
+        }
         AppState::Viewing => {
             match app.active_tab {
                 Tab::Dashboard => render_dashboard(f, app, chunks[1]),
@@ -51,22 +65,27 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             }
             render_confirm_modal(f, app);
         }
-        AppState::Cleaning { current, total, ref item_name } => {
-            render_progress_screen(f, current, total, item_name, chunks[1]);
+        AppState::Cleaning(ref progress) => {
+            render_progress_screen(f, progress, chunks[1]);
         }
     }
 
     // --- Footer ---
     let total_size = ByteSize(app.total_size);
-    let mode_text = if app.dry_run { 
-        Span::styled("DRY-RUN (Safe)", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)) 
-    } else { 
-        Span::styled("DANGER (DELETING)", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD | Modifier::RAPID_BLINK)) 
+    let mode_text = match app.mode {
+        RunMode::DryRun => Span::styled("DRY-RUN (Safe)", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+        RunMode::Trash => Span::styled("TRASH (Recoverable)", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        RunMode::Quarantine => Span::styled("QUARANTINE (Undoable)", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        RunMode::Delete => Span::styled("DANGER (DELETING)", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD | Modifier::RAPID_BLINK)),
     };
 
+    let hint = match &app.notice {
+        Some(notice) => Span::styled(format!("{} | ", notice), Style::default().fg(Color::Cyan)),
+        None => Span::raw("Tab: <Tab>, Nav: <Up/Down>, Toggle: <Space>, Mode: <d>, Clean: <Enter>, Undo: <u> | "),
+    };
     let footer_text = Line::from(vec![
         Span::raw(format!("Total Found: {} | ", total_size)),
-        Span::raw("Tab: <Tab>, Nav: <Up/Down>, Toggle: <Space>, Mode: <d>, Clean: <Enter> | "),
+        hint,
         mode_text,
     ]);
 
@@ -81,7 +100,16 @@ fn render_confirm_modal(f: &mut Frame, app: &App) {
     let selected_items = app.items.iter().filter(|i| i.selected).count();
     let selected_size = ByteSize(app.items.iter().filter(|i| i.selected).map(|i| i.size_bytes).sum());
     
-    let area = centered_rect(60, 25, f.area());
+    // Commands cannot be undone, so show exactly what will run before confirming.
+    let commands: Vec<String> = app
+        .items
+        .iter()
+        .filter(|i| i.selected && i.command.is_some() && !i.status.is_cleaned())
+        .map(|i| i.location())
+        .collect();
+
+    let height = if commands.is_empty() { 25 } else { (30 + 4 * commands.len() as u16).min(80) };
+    let area = centered_rect(60, height, f.area());
     f.render_widget(Clear, area); // This clears the area under the modal
 
     let block = Block::default()
@@ -90,7 +118,7 @@ fn render_confirm_modal(f: &mut Frame, app: &App) {
         .border_style(Style::default().fg(Color::Yellow))
         .padding(Padding::uniform(1));
 
-    let text = vec![
+    let mut text = vec![
         Line::from(vec![
             Span::raw("Are you sure you want to clean "),
             Span::styled(format!("{}", selected_items), Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
@@ -100,11 +128,19 @@ fn render_confirm_modal(f: &mut Frame, app: &App) {
             Span::raw("Total Space: "),
             Span::styled(format!("{}", selected_size), Style::default().add_modifier(Modifier::BOLD).fg(Color::Magenta)),
         ]),
+    ];
+    if !commands.is_empty() {
+        text.push(Line::from(""));
+        text.push(Line::from("Commands to run:"));
+        text.extend(commands.into_iter().map(|c| Line::from(Span::styled(c, Style::default().fg(Color::Yellow)))));
+    }
+    text.extend([
         Line::from(""),
-        if app.dry_run {
-            Line::from(vec![Span::styled("MODE: DRY-RUN (No files will be deleted)", Style::default().fg(Color::Green))])
-        } else {
-            Line::from(vec![Span::styled("WARNING: DANGER MODE (FILES WILL BE DELETED)", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))])
+        match app.mode {
+            RunMode::DryRun => Line::from(vec![Span::styled("MODE: DRY-RUN (No files will be deleted)", Style::default().fg(Color::Green))]),
+            RunMode::Trash => Line::from(vec![Span::styled("MODE: TRASH (Files will be moved to the Trash)", Style::default().fg(Color::Yellow))]),
+            RunMode::Quarantine => Line::from(vec![Span::styled("MODE: QUARANTINE (Files can be restored with <u>)", Style::default().fg(Color::Yellow))]),
+            RunMode::Delete => Line::from(vec![Span::styled("WARNING: DANGER MODE (FILES WILL BE DELETED)", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))]),
         },
         Line::from(""),
         Line::from(vec![
@@ -114,13 +150,13 @@ fn render_confirm_modal(f: &mut Frame, app: &App) {
             Span::styled("[n]", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
             Span::raw(" to cancel."),
         ]),
-    ];
+    ]);
 
     let paragraph = Paragraph::new(text).block(block).alignment(ratatui::layout::Alignment::Center);
     f.render_widget(paragraph, area);
 }
 
-fn render_progress_screen(f: &mut Frame, current: usize, total: usize, item_name: &str, area: Rect) {
+fn render_progress_screen(f: &mut Frame, progress: &CleanProgress, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -130,24 +166,69 @@ fn render_progress_screen(f: &mut Frame, current: usize, total: usize, item_name
         ])
         .split(area);
 
+    let gauge = Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title(format!(" Cleaning Progress {}/{} ", progress.current, progress.total)))
+        .gauge_style(Style::default().fg(Color::Cyan).bg(Color::Black))
+        .ratio(progress.ratio())
+        .label(format!("{} / {}", ByteSize(progress.bytes_done()), ByteSize(progress.total_bytes)));
+
+    let info = Paragraph::new(format!("Deleting: {}", progress.item_name))
+        .alignment(ratatui::layout::Alignment::Center)
+        .style(Style::default().fg(Color::Yellow));
+
+    let eta = match progress.eta() {
+        Some(eta) => format_duration(eta),
+        None => "--".to_string(),
+    };
+    let stats = format!(
+        "Files removed: {} | Throughput: {}/s | ETA: {}",
+        progress.files_removed,
+        ByteSize(progress.throughput() as u64),
+        eta
+    );
+
+    let status = if progress.cancelling {
+        Line::from(Span::styled("Cancelling after the current file...", Style::default().fg(Color::Red)))
+    } else {
+        Line::from(Span::styled("Press <Esc> to cancel", Style::default().fg(Color::DarkGray)))
+    };
+    let details = Paragraph::new(vec![
+        Line::from(progress.file.clone()),
+        Line::from(Span::styled(stats, Style::default().fg(Color::Cyan))),
+        Line::from(""),
+        status,
+    ])
+    .alignment(ratatui::layout::Alignment::Center);
+
+    f.render_widget(info, chunks[0]);
+    f.render_widget(gauge, chunks[1]);
+    f.render_widget(details, chunks[2]);
+}
+
+/// Formats a duration as e.g. `1h 05m`, `3m 20s` or `12s`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+fn render_scan_progress(f: &mut Frame, done: usize, total: usize, current: &str, area: Rect) {
     let percentage = if total > 0 {
-        ((current as f64 / total as f64) * 100.0) as u16
+        ((done as f64 / total as f64) * 100.0) as u16
     } else {
         100
     };
 
     let gauge = Gauge::default()
-        .block(Block::default().borders(Borders::ALL).title(" Cleaning Progress "))
+        .block(Block::default().borders(Borders::ALL).title(format!(" Scanning {}/{} ", done, total)))
         .gauge_style(Style::default().fg(Color::Cyan).bg(Color::Black))
         .percent(percentage)
-        .label(format!("{}/{}", current, total));
+        .label(current.to_string());
 
-    let info = Paragraph::new(format!("Deleting: {}", item_name))
-        .alignment(ratatui::layout::Alignment::Center)
-        .style(Style::default().fg(Color::Yellow));
-
-    f.render_widget(info, chunks[0]);
-    f.render_widget(gauge, chunks[1]);
+    f.render_widget(gauge, area);
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -190,6 +271,10 @@ fn render_dashboard(f: &mut Frame, app: &App, area: Rect) {
         .padding(Padding::uniform(1));
 
     let stats_text = vec![
+        Line::from(vec![
+            Span::raw("System: "),
+            Span::styled(app.system.to_string(), Style::default().fg(Color::Green)),
+        ]),
         Line::from(vec![
             Span::raw("Discovered: "),
             Span::styled(format!("{} items", total_items), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
@@ -199,6 +284,13 @@ fn render_dashboard(f: &mut Frame, app: &App, area: Rect) {
             Span::styled(app.total_size.to_string(), Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
             Span::raw(format!(" ({})", ByteSize(app.total_size))),
         ]),
+        Line::from(vec![
+            Span::raw("On Disk: "),
+            Span::styled(
+                ByteSize(app.items.iter().map(|i| i.allocated_bytes).sum()).to_string(),
+                Style::default().fg(Color::Magenta),
+            ),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::raw("Selected for cleaning: "),
@@ -245,7 +337,7 @@ fn render_dashboard(f: &mut Frame, app: &App, area: Rect) {
     }
 
     let mut data: Vec<(String, u64)> = distribution.into_iter().collect();
-    data.sort_by(|a, b| b.1.cmp(&a.1));
+    data.sort_by_key(|b| std::cmp::Reverse(b.1));
 
     let colors = [Color::Cyan, Color::Magenta, Color::Yellow, Color::Green, Color::Blue, Color::Red];
     let legend_items: Vec<ListItem> = data.iter().enumerate().map(|(idx, (cat, size))| {
@@ -305,15 +397,27 @@ fn render_results(f: &mut Frame, app: &mut App, area: Rect) {
             ResultRow::Item(idx) => {
                 let i = &app.items[*idx];
                 let checkbox = if i.selected { "[x] " } else { "[ ] " };
-                let size = ByteSize(i.size_bytes).to_string();
+                let mut size = if i.allocated_bytes == i.size_bytes {
+                    ByteSize(i.size_bytes).to_string()
+                } else {
+                    format!("{} ({} on disk)", ByteSize(i.size_bytes), ByteSize(i.allocated_bytes))
+                };
+                if let Some(age) = i.age() {
+                    size.push_str(&format!(" | modified {}", age));
+                }
                 
                 let status_style = match i.status {
-                    ItemStatus::Deleted => Style::default().fg(Color::Green),
+                    ItemStatus::Deleted | ItemStatus::Executed(_) => Style::default().fg(Color::Green),
+                    ItemStatus::Skipped(_) => Style::default().fg(Color::Yellow),
                     ItemStatus::Failed(_) => Style::default().fg(Color::Red),
                     _ => Style::default(),
                 };
                 
-                let item_text = format!("{:<20} | {}", i.name, size);
+                // The location tells apart the matches of a glob rule, which share a name.
+                let item_text = match &i.symlink_target {
+                    Some(_) => format!("{:<20} | symlink, not followed | {}", i.name, i.location()),
+                    None => format!("{:<20} | {} | {}", i.name, size, i.location()),
+                };
                 ListItem::new(Line::from(vec![
                     Span::raw("  "), 
                     Span::raw(checkbox),
@@ -343,8 +447,10 @@ fn render_help(f: &mut Frame, _app: &App, area: Rect) {
         Line::from(""),
         Line::from(vec![Span::styled("Actions", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))]),
         Line::from("  Space         : Toggle Selection"),
-        Line::from("  d             : Toggle Dry-Run / Danger Mode"),
+        Line::from("  d             : Cycle Dry-Run / Trash / Quarantine / Danger Mode"),
+        Line::from("  u             : Undo Last Quarantined Run"),
         Line::from("  Enter         : Clean Selected Items"),
+        Line::from("  Esc           : Cancel Cleaning (after the current file)"),
         Line::from("  q             : Quit TidyTUI"),
         Line::from(""),
         Line::from(vec![Span::styled("About", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan))]),